@@ -1,6 +1,49 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+// The one reserved name, it stands for the empty string in a rule alternate
+pub(crate) static EMPTY_NAME: &str = "Empty";
+
+// Quoted literals in a rule alternate are terminals, named by their text in double quotes.
+// No declared name can start with a quote, so these never collide with one
+pub(crate) fn literal_name(text: &str) -> String {
+    let mut name = String::from("\"");
+    for c in text.chars() {
+        if c == '"' || c == '\\' {
+            name.push('\\');
+        }
+        name.push(c);
+    }
+    name.push('"');
+    name
+}
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+// The text a literal name stands for, undoing literal_name
+pub(crate) fn literal_text(name: &str) -> String {
+    let mut text = String::new();
+    let mut chars = name[1..name.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => text.extend(chars.next()),
+            c => text.push(c),
+        }
+    }
+    text
+}
+
+pub(crate) fn is_literal_name(name: &str) -> bool {
+    name.starts_with('"')
+}
+
+// Whether literal_name could have made the name, so that literal_text can undo it
+pub(crate) fn is_valid_literal_name(name: &str) -> bool {
+    name.len() > 2 && name.ends_with('"') && literal_name(&literal_text(name)) == name
+}
+
+/// Names a symbol of a checked grammer. Ids are only meaningful with the alphabet they came from.
+/// They are handed out in the order symbols are declared, so ordering ids orders symbols that way.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct SymbolId {
     id: usize,
 }
@@ -15,55 +58,78 @@ impl SymbolId {
     }
 }
 
+/// Every symbol is a nonterminal or a terminal, except for the one `Empty` symbol.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SymbolType {
+    /// A symbol that rules expand.
     NonTerminal = 1,
+    /// A symbol of the input.
     Terminal = 2,
+    /// The empty string.
     Empty = 3
 }
 
 impl SymbolType {
     fn index(self) -> usize {
         match self {
-            SymbolType::NonTerminal => 1,
-            SymbolType::Terminal => 2,
-            SymbolType::Empty => 3,
+            SymbolType::NonTerminal => 0,
+            SymbolType::Terminal => 1,
+            SymbolType::Empty => 2,
         }
     }
 }
 
-pub struct RawTypedAlphabet {
+pub(crate) struct RawTypedAlphabet {
     name_map: HashMap<String, (SymbolId, SymbolType)>,
     next_id: SymbolId,
 }
 
+impl Default for RawTypedAlphabet {
+    fn default() -> RawTypedAlphabet {
+        RawTypedAlphabet::new()
+    }
+}
+
 impl RawTypedAlphabet {
-    fn new() -> RawTypedAlphabet {
-        RawTypedAlphabet {
+    pub(crate) fn new() -> RawTypedAlphabet {
+        let mut alphabet = RawTypedAlphabet {
             name_map: HashMap::new(),
             next_id: SymbolId::first(),
-        }
+        };
+        alphabet.insert(String::from(EMPTY_NAME), SymbolType::Empty);
+        alphabet
     }
 
-    fn insert(&mut self, sym_name: String, sym_type: SymbolType) {
-        self.name_map.insert(sym_name, (self.next_id, sym_type));
+    pub(crate) fn insert(&mut self, sym_name: String, sym_type: SymbolType) -> SymbolId {
+        let sym_id = self.next_id;
+        self.name_map.insert(sym_name, (sym_id, sym_type));
         self.next_id.increment();
+        sym_id
     }
 
-    fn finalize(self) -> TypedAlphabet {
-        let mut type_sets = Vec::with_capacity(3);
-        type_sets[SymbolType::NonTerminal.index()] = HashSet::new();
-        type_sets[SymbolType::Terminal.index()] = HashSet::new();
-        type_sets[SymbolType::Empty.index()] = HashSet::new();
-        
-        let mut name_map = HashMap::new();
-        let mut id_map = HashMap::new();
-        for (name, &(sym_id, sym_type)) in &self.name_map {
-            name_map.insert(name.clone(), sym_id.clone());
-            id_map.insert(sym_id.clone(), (name.clone(), sym_type.clone()));
-            type_sets[sym_type.index()].insert(sym_id.clone());
+    // Literals are never declared, the first use of one registers it as a terminal
+    pub(crate) fn insert_literal(&mut self, literal_name: &str) -> SymbolId {
+        match self.get(literal_name) {
+            Some((id, _)) => id,
+            None => self.insert(String::from(literal_name), SymbolType::Terminal),
         }
+    }
 
+    pub(crate) fn get(&self, sym_name: &str) -> Option<(SymbolId, SymbolType)> {
+        self.name_map.get(sym_name).cloned()
+    }
+
+    pub(crate) fn finalize(self) -> TypedAlphabet {
+        let mut type_sets = vec![BTreeSet::new(), BTreeSet::new(), BTreeSet::new()];
+
+        let mut name_map = BTreeMap::new();
+        let mut id_map = BTreeMap::new();
+        for (name, &(sym_id, sym_type)) in &self.name_map {
+            name_map.insert(name.clone(), sym_id);
+            id_map.insert(sym_id, (name.clone(), sym_type));
+            type_sets[sym_type.index()].insert(sym_id);
+        }
 
         TypedAlphabet {
             name_map: name_map,
@@ -73,35 +139,42 @@ impl RawTypedAlphabet {
     }
 }
 
+/// The symbols of a checked grammer, looked up by id or by name.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TypedAlphabet {
-    id_map: HashMap<SymbolId, (String, SymbolType)>,
-    name_map: HashMap<String, SymbolId>,
-    type_sets: Vec<HashSet<SymbolId>>
+    id_map: BTreeMap<SymbolId, (String, SymbolType)>,
+    name_map: BTreeMap<String, SymbolId>,
+    type_sets: Vec<BTreeSet<SymbolId>>
 }
 
 impl TypedAlphabet {
-    pub fn name_for_id<'a>(&'a self, id: SymbolId) -> Option<&'a String> {
+    /// The name a symbol was written with. Literals keep their double quotes.
+    pub fn name_for_id(&self, id: SymbolId) -> Option<&String> {
         self.id_map
             .get(&id)
             .map(|result| &result.0)
     }
 
-    pub fn type_for_id<'a>(&'a self, id: SymbolId) -> Option<SymbolType> {
+    /// Whether a symbol is a terminal, a nonterminal or `Empty`.
+    pub fn type_for_id(&self, id: SymbolId) -> Option<SymbolType> {
         self.id_map
             .get(&id)
-            .map(|result| result.1.clone())
+            .map(|result| result.1)
     }
 
-    pub fn id_for_name<'a>(&'a self, name: &str) -> Option<SymbolId> {
+    /// The id of the symbol with this name, if there is one.
+    pub fn id_for_name(&self, name: &str) -> Option<SymbolId> {
         self.name_map
             .get(name)
-            .map(|result| result.clone())
+            .cloned()
     }
 
-    pub fn get_type_set<'a>(&'a self, sym_type: SymbolType) -> &'a HashSet<SymbolId> {
+    /// Every symbol of one type, which iterates in declaration order.
+    pub fn get_type_set(&self, sym_type: SymbolType) -> &BTreeSet<SymbolId> {
         &self.type_sets[sym_type.index()]
     }
 
+    /// Whether a symbol is of the given type.
     pub fn is_type(&self, sym_id: &SymbolId, test_type: SymbolType) -> bool {
         self.type_sets[test_type.index()]
             .contains(sym_id)
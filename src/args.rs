@@ -1,47 +1,204 @@
-use clap::{Arg, App};
+use clap::{Arg, ArgGroup, App, AppSettings, ArgMatches, SubCommand};
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 use std::process::exit;
 
 // Programmer defined constants
-static PROGRAM_NAME: &'static str = "grammer_sets";
+static PROGRAM_NAME: &str = "grammer_sets";
 
 // Derived constants
-static VERSION: &'static str = env!("CARGO_PKG_VERSION");
+static VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub struct Args {
+    pub input_path: String,
     pub input_buffer: Vec<u8>,
+    pub diff_path: Option<String>,
+    pub diff_buffer: Option<Vec<u8>>,
+    pub sentence: Option<String>,
+    pub text_buffer: Option<Vec<u8>>,
+    pub tree_format: Option<String>,
+    pub trace: bool,
+    pub why_first: Option<(String, String)>,
+    pub why_follow: Option<(String, String)>,
+    pub lookahead: Option<usize>,
+    pub pretty: bool,
+    pub infer_terminals: bool,
+    pub slr: bool,
+    pub format: String,
+    pub dot: bool,
+    pub svg: bool,
+    pub report_path: Option<String>,
 }
 
-pub fn parse_args() -> Args {
+// grammer_sets fmt, with the path and text of each file to format
+pub struct FormatArgs {
+    pub files: Vec<(String, Vec<u8>)>,
+    pub check: bool,
+}
+
+pub enum Command {
+    Analyse(Box<Args>),
+    Format(FormatArgs),
+}
+
+fn value_pair(app: &ArgMatches, name: &str) -> Option<(String, String)> {
+    app.values_of(name).map(|mut values| {
+        let symbol = String::from(values.next().unwrap());
+        let entry = String::from(values.next().unwrap());
+        (symbol, entry)
+    })
+}
+
+fn read_file(path: &str) -> Vec<u8> {
+    let input_path = Path::new(path);
+    if ! input_path.exists() {
+        println!("The passed input file:\n{}\nDoes not exist!",
+            input_path.display()
+        );
+        exit(1);
+    }
+
+    let mut input_file = File::open(input_path).unwrap();
+    let mut input_buffer = Vec::new();
+    input_file.read_to_end(&mut input_buffer)
+        .expect("Unable to read from file");
+    input_buffer
+}
+
+fn parse_lookahead(value: &str) -> usize {
+    match value.parse() {
+        Ok(k) if k > 0 => k,
+        _ => {
+            println!("The lookahead k must be a positive integer, not {}", value);
+            exit(1);
+        }
+    }
+}
+
+pub fn parse_args() -> Command {
     let app = App::new(PROGRAM_NAME)
         .version(VERSION)
         .author("Russell W. Bentley <russell_w_bentley@icloud.com>")
         .about("A tool for parsing regular expressions")
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(Arg::with_name("INPUT_FILE")
             .long("input")
             .short("i")
             .value_name("file/path")
             .takes_value(true)
             .required(true))
+        .arg(Arg::with_name("DIFF_FILE")
+            .help("Structurally compare the input grammer against this one")
+            .long("diff")
+            .short("d")
+            .value_name("file/path")
+            .takes_value(true))
+        .arg(Arg::with_name("SENTENCE")
+            .help("Print the derivations of this whitespace separated list of terminals")
+            .long("sentence")
+            .short("s")
+            .value_name("terminals")
+            .takes_value(true))
+        .arg(Arg::with_name("TEXT_FILE")
+            .help("Split this file into terminals with the :Tokens: section, and print its derivations")
+            .long("text")
+            .value_name("file/path")
+            .takes_value(true))
+        .group(ArgGroup::with_name("TERMINALS")
+            .args(&["SENTENCE", "TEXT_FILE"]))
+        .arg(Arg::with_name("TREE_FORMAT")
+            .help("Also print the parse tree of the sentence")
+            .long("tree")
+            .value_name("format")
+            .possible_values(&["ascii", "dot"])
+            .requires("TERMINALS")
+            .takes_value(true))
+        .arg(Arg::with_name("TRACE")
+            .help("Print every pass of the First and Follow set computations")
+            .long("trace")
+            .short("t"))
+        .arg(Arg::with_name("WHY_FIRST")
+            .help("Explain why an entry is in the first set of a symbol")
+            .long("why-first")
+            .value_names(&["symbol", "entry"])
+            .number_of_values(2))
+        .arg(Arg::with_name("WHY_FOLLOW")
+            .help("Explain why an entry is in the follow set of a symbol")
+            .long("why-follow")
+            .value_names(&["symbol", "entry"])
+            .number_of_values(2))
+        .arg(Arg::with_name("LOOKAHEAD")
+            .help("Compute First and Follow sets of k terminal strings, and check for LL(k) conflicts")
+            .long("k")
+            .short("k")
+            .value_name("k")
+            .takes_value(true))
+        .arg(Arg::with_name("PRETTY")
+            .help("Print the grammer back out, with the alternates of each head grouped together")
+            .long("pretty")
+            .short("p"))
+        .arg(Arg::with_name("INFER_TERMINALS")
+            .help("Treat every undeclared symbol that never heads a rule as a terminal")
+            .long("infer-terminals"))
+        .arg(Arg::with_name("SLR")
+            .help("Build the SLR(1) table and report its conflicts and how precedence resolved them")
+            .long("slr"))
+        .arg(Arg::with_name("DOT")
+            .help("Print which nonterminals the rules of each nonterminal use as a Graphviz DOT graph, or with --slr the LR(0) automaton")
+            .long("dot"))
+        .arg(Arg::with_name("SVG")
+            .help("Print a railroad diagram of the rules of each nonterminal as SVG")
+            .long("svg"))
+        .arg(Arg::with_name("REPORT_FILE")
+            .help("Write an HTML page with the grammer, its symbols, sets, LL(1) table and useless symbols")
+            .long("report")
+            .value_name("file/path")
+            .takes_value(true))
+        .arg(Arg::with_name("FORMAT")
+            .help("Print the sets and the LL and SLR(1) conflicts as text, or all together as JSON, or the sets and the LL(1) and SLR(1) tables as LaTeX or Markdown tables")
+            .long("format")
+            .value_name("format")
+            .possible_values(&["text", "json", "latex", "markdown"])
+            .default_value("text"))
+        .subcommand(SubCommand::with_name("fmt")
+            .about("Rewrite grammer files in the canonical layout, keeping their comments")
+            .arg(Arg::with_name("FILES")
+                .value_name("file/path")
+                .multiple(true)
+                .required(true))
+            .arg(Arg::with_name("CHECK")
+                .help("Don't rewrite anything, list the files that aren't formatted and exit with 1 if there are any")
+                .long("check")))
         .get_matches();
 
-    let input_path = Path::new(app.value_of("INPUT_FILE").unwrap());
-    if ! input_path.exists() {
-        println!("The passed input file:\n{}\nDoes not exist!", 
-            input_path.display()
-        );
-        exit(1);
+    if let Some(fmt) = app.subcommand_matches("fmt") {
+        return Command::Format(FormatArgs {
+            files: fmt.values_of("FILES").unwrap()
+                .map(|path| (String::from(path), read_file(path)))
+                .collect(),
+            check: fmt.is_present("CHECK"),
+        });
     }
 
-    let mut input_file = File::open(&input_path).unwrap();
-    let mut input_buffer = Vec::new();
-    input_file.read_to_end(&mut input_buffer)
-        .expect("Unable to read from file");
-
-    Args {
-        input_buffer: input_buffer,
-    }
+    Command::Analyse(Box::new(Args {
+        input_path: String::from(app.value_of("INPUT_FILE").unwrap()),
+        input_buffer: read_file(app.value_of("INPUT_FILE").unwrap()),
+        diff_path: app.value_of("DIFF_FILE").map(String::from),
+        diff_buffer: app.value_of("DIFF_FILE").map(read_file),
+        sentence: app.value_of("SENTENCE").map(String::from),
+        text_buffer: app.value_of("TEXT_FILE").map(read_file),
+        tree_format: app.value_of("TREE_FORMAT").map(String::from),
+        trace: app.is_present("TRACE"),
+        why_first: value_pair(&app, "WHY_FIRST"),
+        why_follow: value_pair(&app, "WHY_FOLLOW"),
+        lookahead: app.value_of("LOOKAHEAD").map(parse_lookahead),
+        pretty: app.is_present("PRETTY"),
+        infer_terminals: app.is_present("INFER_TERMINALS"),
+        slr: app.is_present("SLR"),
+        format: String::from(app.value_of("FORMAT").unwrap()),
+        dot: app.is_present("DOT"),
+        svg: app.is_present("SVG"),
+        report_path: app.value_of("REPORT_FILE").map(String::from),
+    }))
 }
-
@@ -0,0 +1,139 @@
+use alphabet::{EMPTY_NAME, is_literal_name, is_valid_literal_name};
+use grammer::{check, Diagnostic, Grammer};
+use parser::{is_name, NAME_RULES};
+use raw_grammer::{RawGrammer, RawName, RawRule, RawSymbol, Span};
+
+static LITERAL_RULES: &str =
+    "A literal is text between double quotes, with a backslash before any \\ or \" in the text";
+
+/// Builds a grammer in code, without writing it out as text. The names are the same as in a
+/// `.grammer` file, literals included, and `build` checks the grammer the same way too.
+///
+/// ```
+/// use grammer_sets::GrammarBuilder;
+///
+/// let grammer = GrammarBuilder::new()
+///     .terminal("Id")
+///     .rule("E", &["E", "\"+\"", "Id"])
+///     .rule("E", &["Id"])
+///     .build()
+///     .unwrap();
+/// assert_eq!(grammer.rules.len(), 2);
+///
+/// let diagnostics = GrammarBuilder::new().rule("E", &["Id"]).build().err().unwrap();
+/// assert_eq!(diagnostics[0].message, "Id was used in a rule alternate, but was not declared");
+/// ```
+pub struct GrammarBuilder {
+    raw_grammer: RawGrammer,
+    infer_terminals: bool,
+}
+
+impl Default for GrammarBuilder {
+    fn default() -> GrammarBuilder {
+        GrammarBuilder::new()
+    }
+}
+
+impl GrammarBuilder {
+    /// A builder with no symbols and no rules.
+    pub fn new() -> GrammarBuilder {
+        GrammarBuilder {
+            raw_grammer: RawGrammer {
+                starts: Vec::new(),
+                terminals: Vec::new(),
+                non_terminals: Vec::new(),
+                rules: Vec::new(),
+                precedence: Vec::new(),
+                tokens: Vec::new(),
+                skips: Vec::new(),
+                comments: Vec::new(),
+                includes: Vec::new(),
+                files: Vec::new()
+            },
+            infer_terminals: false,
+        }
+    }
+
+    /// Declare a terminal.
+    pub fn terminal(&mut self, name: &str) -> &mut GrammarBuilder {
+        self.raw_grammer.terminals.push(RawName { name: String::from(name), span: Span::default() });
+        self
+    }
+
+    /// Declare a nonterminal. Every rule head is one already, so this is only needed for
+    /// nonterminals that have no rules.
+    pub fn nonterminal(&mut self, name: &str) -> &mut GrammarBuilder {
+        self.raw_grammer.non_terminals.push(RawName { name: String::from(name), span: Span::default() });
+        self
+    }
+
+    /// Set the start symbol. Without one the head of the first rule is used.
+    pub fn start(&mut self, name: &str) -> &mut GrammarBuilder {
+        self.raw_grammer.starts = vec![RawName { name: String::from(name), span: Span::default() }];
+        self
+    }
+
+    /// Add a rule. An empty alternate is the same as one holding only `Empty`.
+    pub fn rule(&mut self, head: &str, alternate: &[&str]) -> &mut GrammarBuilder {
+        let mut alternate: Vec<RawSymbol> = alternate.iter()
+            .map(|name| RawSymbol::Name(String::from(*name)))
+            .collect();
+        if alternate.is_empty() {
+            alternate.push(RawSymbol::Name(String::from(EMPTY_NAME)));
+        }
+
+        self.raw_grammer.rules.push(RawRule {
+            head: String::from(head),
+            child_names: vec![None; alternate.len()],
+            alternate: alternate,
+            label: None,
+            precedence: None,
+            action: None,
+            span: Span::default(),
+            comments: Vec::new()
+        });
+        self
+    }
+
+    /// Treat every undeclared symbol that never heads a rule as a terminal.
+    pub fn infer_terminals(&mut self, infer_terminals: bool) -> &mut GrammarBuilder {
+        self.infer_terminals = infer_terminals;
+        self
+    }
+
+    /// Check the grammer, and return everything wrong with it if it can't be built.
+    pub fn build(&self) -> Result<Grammer, Vec<Diagnostic>> {
+        let raw_grammer = &self.raw_grammer;
+        let names = raw_grammer.terminals.iter()
+            .chain(&raw_grammer.non_terminals)
+            .chain(&raw_grammer.starts)
+            .map(|name| (&name.name, false))
+            .chain(raw_grammer.rules.iter().map(|rule| (&rule.head, false)));
+        let alternate_names = raw_grammer.rules.iter()
+            .flat_map(|rule| rule.alternate.iter().filter_map(RawSymbol::name))
+            .map(|name| (name, true));
+
+        // The text format can't hold a name like "a b" or a literal like "a, so neither can a
+        // built grammer. Only alternates can hold literals
+        let mut diagnostics: Vec<Diagnostic> = Vec::new();
+        for (name, in_alternate) in names.chain(alternate_names) {
+            let (valid, rules) = if in_alternate && is_literal_name(name) {
+                (is_valid_literal_name(name), LITERAL_RULES)
+            } else {
+                (is_name(name), NAME_RULES)
+            };
+            let diagnostic = Diagnostic {
+                location: None,
+                message: format!("{} is not a valid name. {}", name, rules),
+            };
+            if ! valid && ! diagnostics.contains(&diagnostic) {
+                diagnostics.push(diagnostic);
+            }
+        }
+        if ! diagnostics.is_empty() {
+            return Err(diagnostics);
+        }
+
+        check(raw_grammer, self.infer_terminals)
+    }
+}
@@ -0,0 +1,138 @@
+use alphabet::EMPTY_NAME;
+use earley::ParseTree;
+use grammer::Grammer;
+
+fn sentential_form_to_string(grammer: &Grammer, form: &[&ParseTree]) -> String {
+    if form.is_empty() {
+        return String::from(EMPTY_NAME);
+    }
+
+    form.iter()
+        .map(|tree| grammer.name(tree.symbol(grammer)))
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn is_node(tree: &ParseTree) -> bool {
+    match *tree {
+        ParseTree::Node { .. } => true,
+        ParseTree::Leaf(_) => false,
+    }
+}
+
+// Expand one nonterminal per step, either the leftmost or rightmost one, until only
+// terminals are left. Every line after the first is one derivation step
+fn print_derivation(grammer: &Grammer, tree: &ParseTree, leftmost: bool) {
+    let mut form = vec![tree];
+    println!("    {}", sentential_form_to_string(grammer, &form));
+
+    loop {
+        let position = if leftmost {
+            form.iter().position(|t| is_node(t))
+        } else {
+            form.iter().rposition(|t| is_node(t))
+        };
+
+        let position = match position {
+            Some(p) => p,
+            None => break,
+        };
+
+        let children: Vec<&ParseTree> = match *form[position] {
+            ParseTree::Node { ref children, .. } => children.iter().collect(),
+            ParseTree::Leaf(_) => unreachable!(),
+        };
+        form.splice(position..position + 1, children);
+        println!(" => {}", sentential_form_to_string(grammer, &form));
+    }
+}
+
+/// Prints the leftmost and rightmost derivations of a parse tree.
+pub fn print_derivations(grammer: &Grammer, tree: &ParseTree) {
+    println!("Leftmost derivation:");
+    print_derivation(grammer, tree, true);
+    println!("Rightmost derivation:");
+    print_derivation(grammer, tree, false);
+}
+
+// A node of a labelled rule shows the label after its symbol
+fn node_text(grammer: &Grammer, tree: &ParseTree) -> String {
+    let name = grammer.name(tree.symbol(grammer));
+    match *tree {
+        ParseTree::Node { rule, .. } if grammer.rules[rule].label.is_some() => {
+            format!("{} @{}", name, grammer.node_name(rule))
+        },
+        _ => String::from(name),
+    }
+}
+
+fn print_ascii_node(grammer: &Grammer, tree: &ParseTree, child_name: Option<&String>, prefix: &str, last: bool) {
+    let (branch, extension) = if last { ("`-- ", "    ") } else { ("|-- ", "|   ") };
+    match child_name {
+        Some(child_name) => println!("{}{}{}: {}", prefix, branch, child_name, node_text(grammer, tree)),
+        None => println!("{}{}{}", prefix, branch, node_text(grammer, tree)),
+    }
+    print_ascii_children(grammer, tree, &format!("{}{}", prefix, extension));
+}
+
+fn print_ascii_children(grammer: &Grammer, tree: &ParseTree, prefix: &str) {
+    if let ParseTree::Node { rule, ref children } = *tree {
+        if children.is_empty() {
+            println!("{}`-- {}", prefix, EMPTY_NAME);
+        }
+        let child_names = &grammer.rules[rule].child_names;
+        for (index, child) in children.iter().enumerate() {
+            let child_name = child_names[index].as_ref();
+            print_ascii_node(grammer, child, child_name, prefix, index + 1 == children.len());
+        }
+    }
+}
+
+/// Prints a parse tree as indented text.
+pub fn print_ascii_tree(grammer: &Grammer, tree: &ParseTree) {
+    println!("{}", node_text(grammer, tree));
+    print_ascii_children(grammer, tree, "");
+}
+
+// Literal terminals have quotes in their names, which must be escaped inside a DOT label
+pub(crate) fn dot_label(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn print_dot_node(grammer: &Grammer, tree: &ParseTree, next_id: &mut usize) -> usize {
+    let node_id = *next_id;
+    *next_id += 1;
+
+    match *tree {
+        ParseTree::Leaf(id) => {
+            println!("    n{} [label=\"{}\", shape=box];", node_id, dot_label(grammer.name(id)));
+        },
+        ParseTree::Node { rule, ref children } => {
+            println!("    n{} [label=\"{}\"];", node_id, dot_label(&node_text(grammer, tree)));
+            if children.is_empty() {
+                let empty_id = *next_id;
+                *next_id += 1;
+                println!("    n{} [label=\"{}\", shape=plaintext];", empty_id, EMPTY_NAME);
+                println!("    n{} -> n{};", node_id, empty_id);
+            }
+            for (child, child_name) in children.iter().zip(&grammer.rules[rule].child_names) {
+                let child_id = print_dot_node(grammer, child, next_id);
+                match *child_name {
+                    Some(ref child_name) => {
+                        println!("    n{} -> n{} [label=\"{}\"];", node_id, child_id, dot_label(child_name));
+                    },
+                    None => println!("    n{} -> n{};", node_id, child_id),
+                }
+            }
+        }
+    }
+    node_id
+}
+
+/// Prints a parse tree as a Graphviz graph.
+pub fn print_dot_tree(grammer: &Grammer, tree: &ParseTree) {
+    println!("digraph ParseTree {{");
+    let mut next_id = 0;
+    print_dot_node(grammer, tree, &mut next_id);
+    println!("}}");
+}
@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+use alphabet::SymbolType;
+use grammer::Grammer;
+use sets::{entry_name, first_sets, follow_sets, SetMap};
+
+// Rules are matched by their head and alternate, which is exactly their printed form
+fn rule_strings(grammer: &Grammer) -> Vec<String> {
+    grammer.rules.iter()
+        .map(|rule| grammer.rule_to_string(rule))
+        .collect()
+}
+
+// In the order they are declared, like the tables
+fn names(grammer: &Grammer, sym_type: SymbolType) -> Vec<String> {
+    grammer.alphabet.get_type_set(sym_type).iter()
+        .map(|id| String::from(grammer.name(*id)))
+        .collect()
+}
+
+fn set_names(grammer: &Grammer, set_map: &SetMap, name: &str) -> Vec<String> {
+    let id = grammer.alphabet.id_for_name(name).unwrap();
+    set_map.get(&id).unwrap().iter()
+        .map(|entry| entry_name(grammer, entry))
+        .collect()
+}
+
+// Print a '+' line for everything only in new, and a '-' line for everything only in old
+fn print_changes(kind: &str, old: &[String], new: &[String]) -> usize {
+    let mut change_count = 0;
+    for item in new.iter().filter(|item| ! old.contains(item)) {
+        println!("+ {} {}", kind, item);
+        change_count += 1;
+    }
+    for item in old.iter().filter(|item| ! new.contains(item)) {
+        println!("- {} {}", kind, item);
+        change_count += 1;
+    }
+    change_count
+}
+
+fn print_set_changes(
+    label: &str,
+    old: &Grammer,
+    old_sets: &SetMap,
+    new: &Grammer,
+    new_sets: &SetMap,
+) -> usize {
+    let old_non_terminals: HashSet<String> = names(old, SymbolType::NonTerminal)
+        .into_iter()
+        .collect();
+
+    let mut change_count = 0;
+    for name in names(new, SymbolType::NonTerminal) {
+        if ! old_non_terminals.contains(&name) {
+            continue;
+        }
+
+        let old_entries = set_names(old, old_sets, &name);
+        let new_entries = set_names(new, new_sets, &name);
+        if old_entries == new_entries {
+            continue;
+        }
+
+        print!("{}({}):", label, name);
+        for entry in new_entries.iter().filter(|e| ! old_entries.contains(e)) {
+            print!(" +{}", entry);
+        }
+        for entry in old_entries.iter().filter(|e| ! new_entries.contains(e)) {
+            print!(" -{}", entry);
+        }
+        println!();
+        change_count += 1;
+    }
+    change_count
+}
+
+/// Prints the symbols, rules and sets that differ between two grammers.
+pub fn print_diff(old: &Grammer, new: &Grammer) {
+    let mut change_count = 0;
+
+    let old_start = old.name(old.start);
+    let new_start = new.name(new.start);
+    if old_start != new_start {
+        println!("Start: {} -> {}", old_start, new_start);
+        change_count += 1;
+    }
+
+    change_count += print_changes(
+        "Terminal",
+        &names(old, SymbolType::Terminal),
+        &names(new, SymbolType::Terminal),
+    );
+    change_count += print_changes(
+        "NonTerminal",
+        &names(old, SymbolType::NonTerminal),
+        &names(new, SymbolType::NonTerminal),
+    );
+    change_count += print_changes("Rule", &rule_strings(old), &rule_strings(new));
+
+    let old_first_sets = first_sets(old);
+    let new_first_sets = first_sets(new);
+    change_count += print_set_changes("First", old, &old_first_sets, new, &new_first_sets);
+
+    let old_follow_sets = follow_sets(old, &old_first_sets);
+    let new_follow_sets = follow_sets(new, &new_first_sets);
+    change_count += print_set_changes("Follow", old, &old_follow_sets, new, &new_follow_sets);
+
+    if change_count == 0 {
+        println!("The grammers are structurally identical");
+    }
+}
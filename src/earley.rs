@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use alphabet::{literal_name, SymbolId, SymbolType};
+use grammer::{Diagnostic, Grammer};
+
+// An Earley item, the rule we are in, how far through its alternate we are,
+// and the input position the rule started at
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+struct Item {
+    rule: usize,
+    dot: usize,
+    origin: usize,
+}
+
+/// How a sentence derives from the start symbol.
+pub enum ParseTree {
+    /// A terminal of the sentence.
+    Leaf(SymbolId),
+    /// A nonterminal expanded by a rule, with a subtree for each symbol of its alternate.
+    Node {
+        /// The index of the rule in the grammer.
+        rule: usize,
+        /// One subtree for each symbol of the alternate.
+        children: Vec<ParseTree>
+    }
+}
+
+impl ParseTree {
+    /// The symbol at the root of the tree.
+    pub fn symbol(&self, grammer: &Grammer) -> SymbolId {
+        match *self {
+            ParseTree::Leaf(id) => id,
+            ParseTree::Node { rule, .. } => grammer.rules[rule].head,
+        }
+    }
+}
+
+// How an item first got into the chart. Each link points at items that were added before it,
+// at the same position or an earlier one, so following links back always ends
+#[derive(Clone, Copy, Debug)]
+enum Link {
+    Predicted,
+    // The item one position back with the dot before the scanned terminal
+    Scanned(usize),
+    // The item waiting at the origin of the child, and the completed child at this position
+    Completed { previous: usize, child: usize },
+    // The item at this position with the dot before a symbol that derives Empty
+    Nullable(usize),
+}
+
+struct Chart {
+    items: Vec<Vec<Item>>,
+    links: Vec<Vec<Link>>,
+    index: Vec<HashMap<Item, usize>>,
+}
+
+impl Chart {
+    fn new(length: usize) -> Chart {
+        Chart {
+            items: vec![Vec::new(); length + 1],
+            links: vec![Vec::new(); length + 1],
+            index: vec![HashMap::new(); length + 1],
+        }
+    }
+
+    // Only the first way an item is found is kept, which is enough to build one tree
+    fn add(&mut self, position: usize, item: Item, link: Link) {
+        if ! self.index[position].contains_key(&item) {
+            self.index[position].insert(item, self.items[position].len());
+            self.items[position].push(item);
+            self.links[position].push(link);
+        }
+    }
+}
+
+// For every nonterminal that derives Empty, a rule that derives it without going round a cycle.
+// A rule is only picked once every symbol of its alternate has one, so the rules of a pass
+// can use those picked earlier, and empty trees built from them always end
+fn empty_rules(grammer: &Grammer) -> HashMap<SymbolId, usize> {
+    let mut empty_rules = HashMap::new();
+    let mut need_another_pass = true;
+    while need_another_pass {
+        need_another_pass = false;
+        for (index, rule) in grammer.rules.iter().enumerate() {
+            if ! empty_rules.contains_key(&rule.head) && rule.alternate.iter().all(|id| empty_rules.contains_key(id)) {
+                empty_rules.insert(rule.head, index);
+                need_another_pass = true;
+            }
+        }
+    }
+    empty_rules
+}
+
+struct TreeBuilder<'a> {
+    grammer: &'a Grammer,
+    tokens: &'a [SymbolId],
+    chart: &'a Chart,
+    empty_rules: &'a HashMap<SymbolId, usize>,
+}
+
+impl<'a> TreeBuilder<'a> {
+    fn build_node(&self, position: usize, index: usize) -> ParseTree {
+        ParseTree::Node {
+            rule: self.chart.items[position][index].rule,
+            children: self.build_children(position, index),
+        }
+    }
+
+    // The trees of the symbols before the dot of an item, found by walking its links back
+    fn build_children(&self, position: usize, index: usize) -> Vec<ParseTree> {
+        let (mut children, child) = match self.chart.links[position][index] {
+            Link::Predicted => return Vec::new(),
+            Link::Scanned(previous) => (
+                self.build_children(position - 1, previous),
+                ParseTree::Leaf(self.tokens[position - 1])
+            ),
+            Link::Completed { previous, child } => (
+                self.build_children(self.chart.items[position][child].origin, previous),
+                self.build_node(position, child)
+            ),
+            Link::Nullable(previous) => {
+                let item = self.chart.items[position][index];
+                let id = self.grammer.rules[item.rule].alternate[item.dot - 1];
+                (self.build_children(position, previous), self.build_empty(id))
+            },
+        };
+        children.push(child);
+        children
+    }
+
+    fn build_empty(&self, id: SymbolId) -> ParseTree {
+        let rule = self.empty_rules[&id];
+        ParseTree::Node {
+            rule: rule,
+            children: self.grammer.rules[rule].alternate.iter()
+                .map(|child| self.build_empty(*child))
+                .collect(),
+        }
+    }
+}
+
+/// Turn a whitespace separated list of terminal names into their ids.
+pub fn tokens_for_sentence(grammer: &Grammer, sentence: &str) -> Result<Vec<SymbolId>, Vec<Diagnostic>> {
+    let mut tokens = Vec::new();
+    for name in sentence.split_whitespace() {
+        // A literal terminal can be written either quoted or as its bare text
+        let id = grammer.alphabet.id_for_name(name)
+            .or_else(|| grammer.alphabet.id_for_name(&literal_name(name)));
+        match id {
+            Some(id) if grammer.alphabet.is_type(&id, SymbolType::Terminal) => tokens.push(id),
+            _ => {
+                let message = format!("{} in the sentence is not a terminal of the grammer", name);
+                return Err(vec![Diagnostic { location: None, message: message }]);
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Run an Earley recognizer over the tokens, and if the sentence is accepted build
+/// a parse tree for it. Ambiguous sentences get whichever tree is found first.
+pub fn parse_tokens(grammer: &Grammer, tokens: &[SymbolId]) -> Option<ParseTree> {
+    let empty_rules = empty_rules(grammer);
+    let mut chart = Chart::new(tokens.len());
+
+    for (index, rule) in grammer.rules.iter().enumerate() {
+        if rule.head == grammer.start {
+            chart.add(0, Item { rule: index, dot: 0, origin: 0 }, Link::Predicted);
+        }
+    }
+
+    for position in 0..tokens.len() + 1 {
+        let mut next = 0;
+        while next < chart.items[position].len() {
+            let current = next;
+            let item = chart.items[position][current];
+            next += 1;
+
+            let alternate = &grammer.rules[item.rule].alternate;
+            if item.dot == alternate.len() {
+                // Complete, advance everything that was waiting on this head
+                let head = grammer.rules[item.rule].head;
+                let waiting: Vec<(usize, Item)> = chart.items[item.origin].iter()
+                    .cloned()
+                    .enumerate()
+                    .filter(|(_, w)| grammer.rules[w.rule].alternate.get(w.dot) == Some(&head))
+                    .collect();
+                for (previous, w) in waiting {
+                    let link = Link::Completed { previous: previous, child: current };
+                    chart.add(position, Item { dot: w.dot + 1, ..w }, link);
+                }
+                continue;
+            }
+
+            let id = alternate[item.dot];
+            if grammer.alphabet.is_type(&id, SymbolType::Terminal) {
+                // Scan
+                if position < tokens.len() && tokens[position] == id {
+                    chart.add(position + 1, Item { dot: item.dot + 1, ..item }, Link::Scanned(current));
+                }
+                continue;
+            }
+
+            // Predict, stepping straight over nullable symbols so empty rules
+            // completed earlier at this position are not missed
+            for (index, rule) in grammer.rules.iter().enumerate() {
+                if rule.head == id {
+                    chart.add(position, Item { rule: index, dot: 0, origin: position }, Link::Predicted);
+                }
+            }
+            if empty_rules.contains_key(&id) {
+                chart.add(position, Item { dot: item.dot + 1, ..item }, Link::Nullable(current));
+            }
+        }
+    }
+
+    // Of the start rules that cover the whole sentence, the one written first is used
+    let end = tokens.len();
+    let accepted = chart.items[end].iter()
+        .enumerate()
+        .filter(|(_, item)| {
+            let rule = &grammer.rules[item.rule];
+            item.origin == 0 && rule.head == grammer.start && item.dot == rule.alternate.len()
+        })
+        .min_by_key(|(_, item)| item.rule)
+        .map(|(index, _)| index)?;
+
+    let builder = TreeBuilder {
+        grammer: grammer,
+        tokens: tokens,
+        chart: &chart,
+        empty_rules: &empty_rules,
+    };
+    Some(builder.build_node(end, accepted))
+}
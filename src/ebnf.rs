@@ -0,0 +1,180 @@
+use std::collections::HashSet;
+use std::slice;
+use alphabet::EMPTY_NAME;
+use raw_grammer::{RawGrammer, RawRule, RawSymbol, Span};
+
+struct Desugarer {
+    used_names: HashSet<String>,
+    // The rules made up along the way, in the order they were needed
+    rules: Vec<RawRule>,
+}
+
+impl Desugarer {
+    // Fresh nonterminals are named after the head of the rule they came from, E'1, E'2 and so on
+    fn fresh_name(&mut self, base: &str) -> String {
+        let mut count = 1;
+        loop {
+            let name = format!("{}'{}", base, count);
+            if self.used_names.insert(name.clone()) {
+                return name;
+            }
+            count += 1;
+        }
+    }
+
+    fn add_rule(&mut self, head: &str, alternate: Vec<String>, span: Span) {
+        self.rules.push(RawRule {
+            head: String::from(head),
+            child_names: vec![None; alternate.len()],
+            alternate: alternate.into_iter().map(RawSymbol::Name).collect(),
+            label: None,
+            precedence: None,
+            action: None,
+            span: span,
+            comments: Vec::new()
+        });
+    }
+
+    fn lower_alternate(&mut self, base: &str, alternate: &[RawSymbol], span: Span) -> Vec<String> {
+        let mut result = Vec::new();
+        for symbol in alternate {
+            match *symbol {
+                RawSymbol::Name(ref name) => result.push(name.clone()),
+                RawSymbol::Group(ref alternates) => {
+                    result.extend(self.lower_group(base, alternates, span));
+                },
+                // [ x ] becomes N -> x | Empty
+                RawSymbol::Optional(ref alternates) => {
+                    let name = self.fresh_name(base);
+                    self.lower_alternates(base, &name, alternates, span);
+                    self.add_rule(&name, vec![String::from(EMPTY_NAME)], span);
+                    result.push(name);
+                },
+                RawSymbol::Repeat(ref alternates) => {
+                    result.push(self.lower_repeat(base, alternates, span));
+                },
+                // x+ is x { x }
+                RawSymbol::RepeatOne(ref alternates) => {
+                    result.extend(self.lower_group(base, alternates, span));
+                    result.push(self.lower_repeat(base, alternates, span));
+                }
+            }
+        }
+        result
+    }
+
+    fn lower_alternates(&mut self, base: &str, head: &str, alternates: &[Vec<RawSymbol>], span: Span) {
+        for alternate in alternates {
+            let lowered = self.lower_alternate(base, alternate, span);
+            self.add_rule(head, lowered, span);
+        }
+    }
+
+    // A group with a single alternate is just its symbols, anything more needs N -> a | b
+    fn lower_group(&mut self, base: &str, alternates: &[Vec<RawSymbol>], span: Span) -> Vec<String> {
+        if alternates.len() == 1 {
+            return self.lower_alternate(base, &alternates[0], span);
+        }
+
+        let name = self.fresh_name(base);
+        self.lower_alternates(base, &name, alternates, span);
+        vec![name]
+    }
+
+    // { x } becomes N -> x N | Empty, right recursive so it stays LL(1) friendly
+    fn lower_repeat(&mut self, base: &str, alternates: &[Vec<RawSymbol>], span: Span) -> String {
+        let name = self.fresh_name(base);
+        for alternate in alternates {
+            let mut lowered = self.lower_alternate(base, alternate, span);
+            lowered.push(name.clone());
+            self.add_rule(&name, lowered, span);
+        }
+        self.add_rule(&name, vec![String::from(EMPTY_NAME)], span);
+        name
+    }
+}
+
+fn collect_names(alternate: &[RawSymbol], names: &mut HashSet<String>) {
+    for symbol in alternate {
+        match *symbol {
+            RawSymbol::Name(ref name) => {
+                names.insert(name.clone());
+            },
+            RawSymbol::Group(ref alternates)
+            | RawSymbol::Optional(ref alternates)
+            | RawSymbol::Repeat(ref alternates)
+            | RawSymbol::RepeatOne(ref alternates) => {
+                for inner in alternates {
+                    collect_names(inner, names);
+                }
+            }
+        }
+    }
+}
+
+// Lower every EBNF form to plain rules over fresh nonterminals. The generated rules come after
+// the written ones, and carry the span of the alternate they came from. Their heads are left
+// undeclared, so check numbers them after every nonterminal of the grammer as written
+pub(crate) fn desugar(raw_grammer: &RawGrammer) -> RawGrammer {
+    let mut used_names: HashSet<String> = raw_grammer.terminals.iter()
+        .chain(&raw_grammer.non_terminals)
+        .chain(&raw_grammer.starts)
+        .map(|name| name.name.clone())
+        .collect();
+    for rule in &raw_grammer.rules {
+        used_names.insert(rule.head.clone());
+        collect_names(&rule.alternate, &mut used_names);
+    }
+
+    let mut desugarer = Desugarer {
+        used_names: used_names,
+        rules: Vec::new(),
+    };
+
+    let mut rules = Vec::new();
+    for rule in &raw_grammer.rules {
+        let mut alternate = Vec::new();
+        let mut child_names = Vec::new();
+        for (symbol, child_name) in rule.alternate.iter().zip(&rule.child_names) {
+            let mut lowered = desugarer.lower_alternate(&rule.head, slice::from_ref(symbol), rule.span);
+
+            // A named child has to stay one symbol, so anything that lowers to more gets its own nonterminal
+            if child_name.is_some() && lowered.len() != 1 {
+                let name = desugarer.fresh_name(&rule.head);
+                desugarer.add_rule(&name, lowered, rule.span);
+                lowered = vec![name];
+            }
+
+            match *child_name {
+                Some(_) => child_names.push(child_name.clone()),
+                None => child_names.extend(lowered.iter().map(|_| None)),
+            }
+            alternate.extend(lowered);
+        }
+
+        rules.push(RawRule {
+            head: rule.head.clone(),
+            alternate: alternate.into_iter().map(RawSymbol::Name).collect(),
+            child_names: child_names,
+            label: rule.label.clone(),
+            precedence: rule.precedence.clone(),
+            action: rule.action.clone(),
+            span: rule.span,
+            comments: rule.comments.clone()
+        });
+    }
+    rules.extend(desugarer.rules);
+
+    RawGrammer {
+        starts: raw_grammer.starts.clone(),
+        terminals: raw_grammer.terminals.clone(),
+        non_terminals: raw_grammer.non_terminals.clone(),
+        rules: rules,
+        precedence: raw_grammer.precedence.clone(),
+        tokens: raw_grammer.tokens.clone(),
+        skips: raw_grammer.skips.clone(),
+        comments: raw_grammer.comments.clone(),
+        includes: raw_grammer.includes.clone(),
+        files: raw_grammer.files.clone()
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::{HashMap, VecDeque};
+use alphabet::{EMPTY_NAME, SymbolId, SymbolType};
+use grammer::Grammer;
+use sets::{entry_name, first_sets, follow_sets, Reason, SetEntry, SetMap};
+
+fn names(grammer: &Grammer, ids: &[SymbolId]) -> String {
+    ids.iter()
+        .map(|id| grammer.name(*id))
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn entry_for_name(grammer: &Grammer, name: &str) -> Option<SetEntry> {
+    if name == EMPTY_NAME {
+        return Some(SetEntry::Empty);
+    }
+    if name == "End" {
+        return Some(SetEntry::End);
+    }
+    match grammer.alphabet.id_for_name(name) {
+        Some(id) if grammer.alphabet.is_type(&id, SymbolType::Terminal) => Some(SetEntry::Id(id)),
+        _ => {
+            println!("ERROR: {} is not a terminal, Empty or End", name);
+            None
+        }
+    }
+}
+
+fn symbol_for_name(grammer: &Grammer, name: &str) -> Option<SymbolId> {
+    let id = grammer.alphabet.id_for_name(name);
+    if id.is_none() {
+        println!("ERROR: {} is not a symbol of the grammer", name);
+    }
+    id
+}
+
+// Which set a step of a chain is about
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Set {
+    First,
+    Follow,
+}
+
+// A step of a chain, the set of a symbol the entry is in and why
+type Step = (Set, SymbolId, Reason);
+
+struct Sets {
+    first: SetMap,
+    follow: SetMap,
+}
+
+impl Sets {
+    fn contains(&self, set: Set, symbol: SymbolId, entry: SetEntry) -> bool {
+        let set_map = match set {
+            Set::First => &self.first,
+            Set::Follow => &self.follow,
+        };
+        set_map.get(&symbol).is_some_and(|set| set.contains(&entry))
+    }
+
+    fn is_nullable(&self, symbol: SymbolId) -> bool {
+        self.contains(Set::First, symbol, SetEntry::Empty)
+    }
+}
+
+// Every reason the entry is in the first set of the symbol, in the order of the rules, with the
+// first set the reason leans on when it doesn't settle it by itself
+fn first_reasons(grammer: &Grammer, sets: &Sets, symbol: SymbolId, entry: SetEntry) -> Vec<(Reason, Option<(Set, SymbolId)>)> {
+    let mut reasons = Vec::new();
+    if entry == SetEntry::Id(symbol) {
+        reasons.push((Reason::Terminal, None));
+    }
+
+    for (index, rule) in grammer.rules.iter().enumerate().filter(|(_, rule)| rule.head == symbol) {
+        if entry == SetEntry::Empty {
+            if rule.alternate.iter().all(|id| sets.is_nullable(*id)) {
+                reasons.push((Reason::Nullable { rule: index }, None));
+            }
+            continue;
+        }
+
+        for (position, id) in rule.alternate.iter().enumerate() {
+            let reason = Reason::FirstOf { rule: index, position: position };
+            if grammer.alphabet.is_type(id, SymbolType::Terminal) {
+                if entry == SetEntry::Id(*id) {
+                    reasons.push((reason, None));
+                }
+            } else if sets.contains(Set::First, *id, entry) {
+                reasons.push((reason, Some((Set::First, *id))));
+            }
+
+            if ! sets.is_nullable(*id) {
+                break;
+            }
+        }
+    }
+    reasons
+}
+
+// The same for the follow set of the symbol, where a reason can lean on a first set or on
+// the follow set of a head
+fn follow_reasons(grammer: &Grammer, sets: &Sets, symbol: SymbolId, entry: SetEntry) -> Vec<(Reason, Option<(Set, SymbolId)>)> {
+    let mut reasons = Vec::new();
+    if symbol == grammer.start && entry == SetEntry::End {
+        reasons.push((Reason::Start, None));
+    }
+
+    for (index, rule) in grammer.rules.iter().enumerate() {
+        for position in (0..rule.alternate.len()).filter(|position| rule.alternate[*position] == symbol) {
+            let mut rest_nullable = true;
+            for source in position + 1..rule.alternate.len() {
+                let id = rule.alternate[source];
+                let reason = Reason::FollowFirst { rule: index, position: position, source: source };
+                if grammer.alphabet.is_type(&id, SymbolType::Terminal) {
+                    if entry == SetEntry::Id(id) {
+                        reasons.push((reason, None));
+                    }
+                } else if sets.contains(Set::First, id, entry) {
+                    reasons.push((reason, Some((Set::First, id))));
+                }
+
+                if ! sets.is_nullable(id) {
+                    rest_nullable = false;
+                    break;
+                }
+            }
+
+            if rest_nullable && sets.contains(Set::Follow, rule.head, entry) {
+                reasons.push((Reason::FollowHead { rule: index, position: position }, Some((Set::Follow, rule.head))));
+            }
+        }
+    }
+    reasons
+}
+
+// Breadth first from the question, so the first reason found that needs nothing further ends
+// the shortest chain there is. The entry is in the set, so some chain always ends
+fn shortest_chain(grammer: &Grammer, sets: &Sets, set: Set, symbol: SymbolId, entry: SetEntry) -> Vec<Step> {
+    let start = (set, symbol);
+    let mut previous: HashMap<(Set, SymbolId), Step> = HashMap::new();
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+
+    while let Some((set, symbol)) = queue.pop_front() {
+        let reasons = match set {
+            Set::First => first_reasons(grammer, sets, symbol, entry),
+            Set::Follow => follow_reasons(grammer, sets, symbol, entry),
+        };
+
+        for (reason, next) in reasons {
+            let next = match next {
+                Some(next) => next,
+                None => {
+                    let mut chain = vec![(set, symbol, reason)];
+                    let mut current = (set, symbol);
+                    while current != start {
+                        let step = previous[&current];
+                        current = (step.0, step.1);
+                        chain.push(step);
+                    }
+                    chain.reverse();
+                    return chain;
+                }
+            };
+            if next != start && ! previous.contains_key(&next) {
+                previous.insert(next, (set, symbol, reason));
+                queue.push_back(next);
+            }
+        }
+    }
+    unreachable!("Every entry of a set has a chain of reasons that ends")
+}
+
+// One line of a chain, ending with the set the next line explains, if there is one
+fn print_step(grammer: &Grammer, entry: SetEntry, (set, symbol, reason): Step) {
+    let entry_str = entry_name(grammer, &entry);
+    let set_str = match set {
+        Set::First => format!("{} ∈ First({})", entry_str, grammer.name(symbol)),
+        Set::Follow => format!("{} ∈ Follow({})", entry_str, grammer.name(symbol)),
+    };
+
+    match reason {
+        Reason::Terminal => println!("{} because it is a terminal", set_str),
+        Reason::Start => println!("{} because it is the start symbol", set_str),
+        Reason::Nullable { rule } => {
+            let rule_str = grammer.rule_to_string(&grammer.rules[rule]);
+            if grammer.rules[rule].alternate.is_empty() {
+                println!("{} because of rule `{}`", set_str, rule_str);
+            } else {
+                println!("{} because of rule `{}`, every symbol of which is nullable", set_str, rule_str);
+            }
+        },
+        Reason::FirstOf { rule, position } => {
+            let alternate = &grammer.rules[rule].alternate;
+            print!("{} because of rule `{}`", set_str, grammer.rule_to_string(&grammer.rules[rule]));
+            if position > 0 {
+                print!(", where {} can be Empty", names(grammer, &alternate[..position]));
+            }
+            print_next_first(grammer, &entry_str, alternate[position]);
+        },
+        Reason::FollowFirst { rule, position, source } => {
+            let alternate = &grammer.rules[rule].alternate;
+            print!("{} because of rule `{}`", set_str, grammer.rule_to_string(&grammer.rules[rule]));
+            if source > position + 1 {
+                print!(", where {} can be Empty", names(grammer, &alternate[position + 1..source]));
+            }
+            print_next_first(grammer, &entry_str, alternate[source]);
+        },
+        Reason::FollowHead { rule, position } => {
+            let alternate = &grammer.rules[rule].alternate;
+            let head = grammer.rules[rule].head;
+            print!("{} because of rule `{}`", set_str, grammer.rule_to_string(&grammer.rules[rule]));
+            if position + 1 < alternate.len() {
+                print!(", where {} can be Empty", names(grammer, &alternate[position + 1..]));
+            }
+            println!(", and {} ∈ Follow({})", entry_str, grammer.name(head));
+        },
+    }
+}
+
+// A terminal ends the chain, a nonterminal's first set is the next line
+fn print_next_first(grammer: &Grammer, entry_str: &str, source: SymbolId) {
+    if grammer.alphabet.is_type(&source, SymbolType::Terminal) {
+        println!();
+    } else {
+        println!(", and {} ∈ First({})", entry_str, grammer.name(source));
+    }
+}
+
+fn print_chain(grammer: &Grammer, sets: &Sets, set: Set, symbol: SymbolId, entry: SetEntry) {
+    for step in shortest_chain(grammer, sets, set, symbol, entry) {
+        print_step(grammer, entry, step);
+    }
+}
+
+fn build_sets(grammer: &Grammer) -> Sets {
+    let first = first_sets(grammer);
+    let follow = follow_sets(grammer, &first);
+    Sets { first: first, follow: follow }
+}
+
+/// Prints the shortest chain of rules that puts an entry in the first set of a symbol. Returns
+/// false when the entry is not in the set or a name is unknown.
+pub fn why_first(grammer: &Grammer, symbol_str: &str, entry_str: &str) -> bool {
+    let (symbol, entry) = match (symbol_for_name(grammer, symbol_str), entry_for_name(grammer, entry_str)) {
+        (Some(s), Some(e)) => (s, e),
+        _ => return false,
+    };
+
+    let sets = build_sets(grammer);
+    if ! sets.contains(Set::First, symbol, entry) {
+        println!("{} ∉ First({})", entry_str, symbol_str);
+        return false;
+    }
+
+    print_chain(grammer, &sets, Set::First, symbol, entry);
+    true
+}
+
+/// Prints the shortest chain of rules that puts an entry in the follow set of a symbol, like
+/// `why_first`.
+pub fn why_follow(grammer: &Grammer, symbol_str: &str, entry_str: &str) -> bool {
+    let (symbol, entry) = match (symbol_for_name(grammer, symbol_str), entry_for_name(grammer, entry_str)) {
+        (Some(s), Some(e)) => (s, e),
+        _ => return false,
+    };
+
+    let sets = build_sets(grammer);
+    if ! sets.contains(Set::Follow, symbol, entry) {
+        println!("{} ∉ Follow({})", entry_str, symbol_str);
+        return false;
+    }
+
+    print_chain(grammer, &sets, Set::Follow, symbol, entry);
+    true
+}
@@ -0,0 +1,381 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use alphabet::{EMPTY_NAME, is_literal_name, RawTypedAlphabet, SymbolId, SymbolType, TypedAlphabet};
+use ebnf::desugar;
+use raw_grammer::{Associativity, RawGrammer, RawSymbol, Span, TokenPattern};
+use regex::Regex;
+
+/// Where a terminal or rule sits in the `:Precedence:` section. Higher levels bind tighter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Precedence {
+    /// Levels count up from 1, in the order of the `:Precedence:` section.
+    pub level: usize,
+    /// How operators on the same level group.
+    pub associativity: Associativity
+}
+
+/// One alternate of a head. The annotations are carried over from the `RawRule`, with a child
+/// name for each symbol of the alternate. `Empty` is not a symbol here, so an empty rule has an
+/// empty alternate.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Rule {
+    /// The nonterminal the rule expands.
+    pub head: SymbolId,
+    /// What the head expands to.
+    pub alternate: Vec<SymbolId>,
+    /// A name for each symbol of the alternate, when it was given one.
+    pub child_names: Vec<Option<String>>,
+    /// The name given to the rule, if any.
+    pub label: Option<String>,
+    /// The precedence of the rule, for resolving shift/reduce conflicts.
+    pub precedence: Option<Precedence>,
+    /// The code to run when the rule is reduced, as written.
+    pub action: Option<String>
+}
+
+/// A checked grammer. Only terminals have a precedence, rules get theirs when the grammer is
+/// checked. The tokens are the terminals given a pattern in the `:Tokens:` section, in order.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Grammer {
+    /// Every symbol, with its name and type.
+    pub alphabet: TypedAlphabet,
+    /// The symbol every sentence derives from.
+    pub start: SymbolId,
+    /// The rules, in the order they were written.
+    pub rules: Vec<Rule>,
+    /// The precedence of each terminal in the `:Precedence:` section.
+    pub precedence: BTreeMap<SymbolId, Precedence>,
+    /// The pattern of each token.
+    pub tokens: Vec<(SymbolId, TokenPattern)>,
+    /// Patterns the lexer skips over, like whitespace.
+    pub skips: Vec<TokenPattern>
+}
+
+impl Grammer {
+    /// The name of a symbol of this grammer.
+    pub fn name(&self, id: SymbolId) -> &str {
+        self.alphabet.name_for_id(id)
+            .expect("Every id in a checked grammer has a name")
+    }
+
+    /// What a node for the rule should be called by anything generating code or exporting trees.
+    pub fn node_name(&self, rule: usize) -> String {
+        match self.rules[rule].label {
+            Some(ref label) => label.clone(),
+            None => format!("rule_{}", rule),
+        }
+    }
+
+    /// A rule as it would be written in a grammer file.
+    pub fn rule_to_string(&self, rule: &Rule) -> String {
+        let mut result = format!("{} ->", self.name(rule.head));
+        if rule.alternate.is_empty() {
+            result.push(' ');
+            result.push_str(EMPTY_NAME);
+        }
+        for id in &rule.alternate {
+            result.push(' ');
+            result.push_str(self.name(*id));
+        }
+        result.push('.');
+        result
+    }
+}
+
+/// A problem found while checking a grammer, and where it is if the grammer came from a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Diagnostic {
+    /// The file, line and column of the problem.
+    pub location: Option<String>,
+    /// What is wrong.
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.location {
+            Some(ref location) => write!(f, "ERROR: {}: {}", location, self.message),
+            None => write!(f, "ERROR: {}", self.message),
+        }
+    }
+}
+
+struct Errors<'a> {
+    raw_grammer: &'a RawGrammer,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Errors<'a> {
+    // A grammer that was never read from a file has no locations to give
+    fn at(&mut self, span: Span, message: String) {
+        let location = if self.raw_grammer.files.is_empty() {
+            None
+        } else {
+            Some(self.raw_grammer.location(span))
+        };
+        self.diagnostics.push(Diagnostic { location: location, message: message });
+    }
+
+    fn general(&mut self, message: String) {
+        self.diagnostics.push(Diagnostic { location: None, message: message });
+    }
+}
+
+// With infer_terminals, any undeclared symbol that is never the head of a rule is a terminal
+pub(crate) fn check(raw_grammer: &RawGrammer, infer_terminals: bool) -> Result<Grammer, Vec<Diagnostic>> {
+    // Everything after this works on plain BNF, but symbols are numbered in the order written
+    let written = raw_grammer;
+    let raw_grammer = &desugar(written);
+
+    // Every error is collected, and if there are any the grammer is not built
+    let mut errors = Errors {
+        raw_grammer: raw_grammer,
+        diagnostics: Vec::new(),
+    };
+
+    let mut alphabet = RawTypedAlphabet::new();
+    let mut rules = Vec::new();
+
+    for terminal in &raw_grammer.terminals {
+        let name = &terminal.name;
+        match alphabet.get(name) {
+            Some((_, SymbolType::Empty)) => {
+                errors.at(terminal.span, format!("{} is a reserved name, it cannot be declared as a terminal", name));
+            },
+            Some(_) => (),
+            None => {
+                alphabet.insert(name.clone(), SymbolType::Terminal);
+            }
+        }
+    }
+
+    for non_terminal in &raw_grammer.non_terminals {
+        let name = &non_terminal.name;
+        match alphabet.get(name) {
+            Some((_, SymbolType::Empty)) => {
+                errors.at(non_terminal.span, format!("{} is a reserved name, it cannot be declared as a nonterminal", name));
+            },
+            Some((_, SymbolType::Terminal)) => {
+                errors.at(non_terminal.span, format!("{} is listed as both a terminal and a non-terminal", name));
+            },
+            Some((_, SymbolType::NonTerminal)) => (),
+            None => {
+                alphabet.insert(name.clone(), SymbolType::NonTerminal);
+            }
+        }
+    }
+
+    // Ensure the start symbol is properly setup as a nonterminal. I don't (currently) care if it
+    // is also declared with the other non terminals, as long as it wasn't listed as a terminal.
+    // Without a :Start: section the head of the first rule is the start symbol
+    let (start_name, start_span) = match (raw_grammer.starts.first(), raw_grammer.rules.first()) {
+        (Some(start), _) => (&start.name, start.span),
+        (None, Some(rule)) => (&rule.head, rule.span),
+        (None, None) => {
+            errors.general(String::from("there is no start symbol, and no rules to take one from"));
+            return Err(errors.diagnostics);
+        }
+    };
+    for start in raw_grammer.starts.iter().skip(1).filter(|start| start.name != *start_name) {
+        errors.at(start.span, format!("the start symbol is given as both {} and {}", start_name, start.name));
+    }
+    let start_id = match alphabet.get(start_name) {
+        Some((id, SymbolType::NonTerminal)) => id,
+        Some((id, _)) => {
+            errors.at(start_span, format!("{} is listed as both a terminal and the start symbol", start_name));
+            id
+        },
+        None => alphabet.insert(start_name.clone(), SymbolType::NonTerminal),
+    };
+
+    // Nonterminals don't need declaring either, every rule head is one
+    for rule in &raw_grammer.rules {
+        if alphabet.get(&rule.head).is_none() {
+            alphabet.insert(rule.head.clone(), SymbolType::NonTerminal);
+        }
+    }
+
+    // Giving a name a pattern declares it as a terminal
+    let mut tokens: Vec<(SymbolId, TokenPattern)> = Vec::new();
+    for token in &raw_grammer.tokens {
+        let id = match alphabet.get(&token.name) {
+            Some((id, SymbolType::Terminal)) => id,
+            Some(_) => {
+                errors.at(token.span, format!("{} is given a pattern, but only terminals can have one", token.name));
+                continue;
+            },
+            None => alphabet.insert(token.name.clone(), SymbolType::Terminal),
+        };
+        if tokens.iter().any(|(other, _)| *other == id) {
+            errors.at(token.span, format!("{} is given more than one pattern", token.name));
+        }
+        if let TokenPattern::Regex(ref text) = token.pattern {
+            if let Err(err) = Regex::new(text) {
+                errors.at(token.span, format!("the pattern of {} is not a valid regex\n{}", token.name, err));
+            }
+        }
+        tokens.push((id, token.pattern.clone()));
+    }
+    for skip in &raw_grammer.skips {
+        if let TokenPattern::Regex(ref text) = skip.pattern {
+            if let Err(err) = Regex::new(text) {
+                errors.at(skip.span, format!("the %skip pattern /{}/ is not a valid regex\n{}", text, err));
+            }
+        }
+    }
+
+    // Quoted literals never need declaring, and neither do the other terminals when inferring them.
+    // They are taken from the rules as written, since desugaring moves the insides of groups to the end
+    let heads: HashSet<&str> = raw_grammer.rules.iter()
+        .map(|rule| rule.head.as_str())
+        .collect();
+    for rule in &written.rules {
+        for name in rule.alternate.iter().flat_map(RawSymbol::names) {
+            if alphabet.get(name).is_some() {
+                continue;
+            }
+
+            if is_literal_name(name) {
+                alphabet.insert_literal(name);
+            } else if infer_terminals && ! heads.contains(name.as_str()) {
+                alphabet.insert(name.clone(), SymbolType::Terminal);
+            }
+        }
+    }
+
+    // Levels count up from the first line of the :Precedence: section. A name there doesn't have
+    // to be a symbol of the grammer, it can be there just to be used with %prec
+    let mut precedence_by_name: HashMap<&str, Precedence> = HashMap::new();
+    for (index, level) in raw_grammer.precedence.iter().enumerate() {
+        let precedence = Precedence {
+            level: index + 1,
+            associativity: level.associativity
+        };
+        for name in &level.names {
+            if let Some((_, SymbolType::NonTerminal)) | Some((_, SymbolType::Empty)) = alphabet.get(&name.name) {
+                errors.at(name.span, format!("{} was given a precedence, but only terminals can have one", name.name));
+            }
+            let old = precedence_by_name.insert(name.name.as_str(), precedence);
+            if old.is_some_and(|old| old != precedence) {
+                errors.at(name.span, format!("{} was given more than one precedence", name.name));
+            }
+        }
+    }
+
+    // We need at least one rule that is based on the start symbol
+    let mut found_start_rule = false;
+    for rule in &raw_grammer.rules {
+        if rule.head == *start_name {
+            found_start_rule = true;
+        }
+
+        // Ensure the head is not a terminal
+        let head_id = match alphabet.get(&rule.head) {
+            Some((id, SymbolType::NonTerminal)) => id,
+            Some((id, SymbolType::Terminal)) => {
+                errors.at(rule.span, format!("{} was listed as a terminal, but also used as a rule head", rule.head));
+                id
+            },
+            Some((id, SymbolType::Empty)) => {
+                errors.at(rule.span, format!("{} cannot be the head of a rule", rule.head));
+                id
+            },
+            None => unreachable!("Every rule head was added to the alphabet above"),
+        };
+
+        // Ensure all the alternates were delcared
+        // if so, add their ids to the vec
+        let mut alternate_ids = Vec::new();
+        let mut child_names = Vec::new();
+        let alternate_length = rule.alternate.len();
+        let names = rule.alternate.iter().filter_map(RawSymbol::name);
+        for (name, child_name) in names.zip(&rule.child_names) {
+            match alphabet.get(name) {
+                Some((_, SymbolType::Empty)) => {
+                    if child_name.is_some() {
+                        errors.at(rule.span, format!("{} is not a child, so it cannot be given a name", name));
+                    }
+                    if alternate_length != 1 {
+                        errors.at(rule.span, format!("{} was not the only symbol in an a rule alternate", name));
+                    }
+                },
+                Some((id, _)) => {
+                    alternate_ids.push(id);
+                    child_names.push(child_name.clone());
+                },
+                None => {
+                    errors.at(rule.span, format!("{} was used in a rule alternate, but was not declared", name));
+                }
+            }
+        }
+
+        for (index, child_name) in child_names.iter().enumerate() {
+            if let Some(ref child_name) = *child_name {
+                if child_names[..index].contains(&Some(child_name.clone())) {
+                    errors.at(rule.span, format!("{} names more than one child of the rule", child_name));
+                }
+            }
+        }
+
+        // Labels become the names of AST nodes, so the alternates of a head need different ones
+        if let Some(ref label) = rule.label {
+            let taken = rules.iter().any(|other: &Rule| other.head == head_id && other.label.as_ref() == Some(label));
+            if taken {
+                errors.at(rule.span, format!("@{} labels more than one alternate of {}", label, rule.head));
+            }
+        }
+
+        // A rule takes the precedence of its %prec name, or else that of its last terminal with one
+        let precedence = match rule.precedence {
+            Some(ref name) => match precedence_by_name.get(name.as_str()) {
+                Some(precedence) => Some(*precedence),
+                None => {
+                    errors.at(rule.span, format!("%prec {} is not in the :Precedence: section", name));
+                    None
+                }
+            },
+            None => rule.alternate.iter()
+                .filter_map(RawSymbol::name)
+                .rev()
+                .find_map(|name| precedence_by_name.get(name.as_str()).cloned()),
+        };
+
+        // Build an id based rule
+        rules.push(
+            Rule {
+                head: head_id,
+                alternate: alternate_ids,
+                child_names: child_names,
+                label: rule.label.clone(),
+                precedence: precedence,
+                action: rule.action.clone()
+            }
+        );
+    }
+
+    if ! found_start_rule {
+        errors.at(start_span, format!("there was no rule with the start symbol, {}, as the head", start_name));
+    }
+
+    if ! errors.diagnostics.is_empty() {
+        return Err(errors.diagnostics);
+    }
+
+    let precedence = precedence_by_name.iter()
+        .filter_map(|(name, precedence)| match alphabet.get(name) {
+            Some((id, SymbolType::Terminal)) => Some((id, *precedence)),
+            _ => None,
+        })
+        .collect();
+
+    Ok(Grammer {
+        alphabet: alphabet.finalize(),
+        start: start_id,
+        rules: rules,
+        precedence: precedence,
+        tokens: tokens,
+        skips: raw_grammer.skips.iter().map(|skip| skip.pattern.clone()).collect()
+    })
+}
@@ -0,0 +1,199 @@
+use std::collections::{HashMap, HashSet};
+use alphabet::{SymbolId, SymbolType};
+use derivation::dot_label;
+use grammer::Grammer;
+use sets::{first_sets, nullable_set};
+
+// An edge A -> B for every rule of A that mentions B. It is a left corner edge when everything
+// before B in some alternate of A is nullable, so that deriving A can start by deriving B
+pub(crate) struct Edge {
+    pub(crate) from: SymbolId,
+    pub(crate) to: SymbolId,
+    pub(crate) left_corner: bool,
+}
+
+pub(crate) struct DependencyGraph {
+    // In the order they are declared
+    pub(crate) nodes: Vec<SymbolId>,
+    pub(crate) edges: Vec<Edge>,
+    pub(crate) reachable: HashSet<SymbolId>,
+    pub(crate) productive: HashSet<SymbolId>,
+    // Only the components with a cycle in them, a single node without a loop is not one
+    pub(crate) components: Vec<Vec<SymbolId>>,
+    // The indices of the left corner edges that lie on a left corner cycle
+    pub(crate) left_recursive: HashSet<usize>,
+}
+
+// Every nonterminal a set of nonterminals can get to, following only the edges that pass the filter
+fn reach<F: Fn(&Edge) -> bool>(edges: &[Edge], from: &[SymbolId], follow: F) -> HashSet<SymbolId> {
+    let mut reached: HashSet<SymbolId> = from.iter().cloned().collect();
+    let mut stack = from.to_vec();
+    while let Some(id) = stack.pop() {
+        for edge in edges.iter().filter(|edge| edge.from == id && follow(edge)) {
+            if reached.insert(edge.to) {
+                stack.push(edge.to);
+            }
+        }
+    }
+    reached
+}
+
+// A nonterminal is productive once one of its rules has only terminals and productive nonterminals
+fn productive_set(grammer: &Grammer) -> HashSet<SymbolId> {
+    let mut productive = HashSet::new();
+    let mut need_another_pass = true;
+    while need_another_pass {
+        need_another_pass = false;
+        for rule in &grammer.rules {
+            let all_productive = rule.alternate.iter()
+                .all(|id| grammer.alphabet.is_type(id, SymbolType::Terminal) || productive.contains(id));
+            if all_productive && productive.insert(rule.head) {
+                need_another_pass = true;
+            }
+        }
+    }
+    productive
+}
+
+struct Tarjan<'a> {
+    edges: &'a [Edge],
+    index: HashMap<SymbolId, usize>,
+    low_link: HashMap<SymbolId, usize>,
+    stack: Vec<SymbolId>,
+    components: Vec<Vec<SymbolId>>,
+}
+
+impl<'a> Tarjan<'a> {
+    fn visit(&mut self, id: SymbolId) {
+        let index = self.index.len();
+        self.index.insert(id, index);
+        self.low_link.insert(id, index);
+        self.stack.push(id);
+
+        for edge in self.edges.iter().filter(|edge| edge.from == id) {
+            if ! self.index.contains_key(&edge.to) {
+                self.visit(edge.to);
+                let low_link = self.low_link[&id].min(self.low_link[&edge.to]);
+                self.low_link.insert(id, low_link);
+            } else if self.stack.contains(&edge.to) {
+                let low_link = self.low_link[&id].min(self.index[&edge.to]);
+                self.low_link.insert(id, low_link);
+            }
+        }
+
+        if self.low_link[&id] == self.index[&id] {
+            let position = self.stack.iter().position(|other| *other == id).unwrap();
+            let component = self.stack.split_off(position);
+            self.components.push(component);
+        }
+    }
+}
+
+pub(crate) fn dependency_graph(grammer: &Grammer) -> DependencyGraph {
+    let nodes: Vec<SymbolId> = grammer.alphabet.get_type_set(SymbolType::NonTerminal).iter()
+        .cloned()
+        .collect();
+    let nullable = nullable_set(&first_sets(grammer));
+
+    let mut edges: Vec<Edge> = Vec::new();
+    for rule in &grammer.rules {
+        for (position, id) in rule.alternate.iter().enumerate() {
+            if ! grammer.alphabet.is_type(id, SymbolType::NonTerminal) {
+                continue;
+            }
+            let left_corner = rule.alternate[..position].iter().all(|before| nullable.contains(before));
+            match edges.iter_mut().find(|edge| edge.from == rule.head && edge.to == *id) {
+                Some(edge) => edge.left_corner = edge.left_corner || left_corner,
+                None => edges.push(Edge { from: rule.head, to: *id, left_corner: left_corner }),
+            }
+        }
+    }
+
+    let reachable = reach(&edges, &[grammer.start], |_| true);
+    let productive = productive_set(grammer);
+
+    let mut tarjan = Tarjan {
+        edges: &edges,
+        index: HashMap::new(),
+        low_link: HashMap::new(),
+        stack: Vec::new(),
+        components: Vec::new(),
+    };
+    for id in &nodes {
+        if ! tarjan.index.contains_key(id) {
+            tarjan.visit(*id);
+        }
+    }
+    let mut components: Vec<Vec<SymbolId>> = tarjan.components.into_iter()
+        .filter(|component| {
+            component.len() > 1 || edges.iter().any(|edge| edge.from == component[0] && edge.to == component[0])
+        })
+        .collect();
+    // Tarjan finds components in reverse, put them and their nodes back in the order of the nodes
+    for component in &mut components {
+        component.sort_by_key(|id| nodes.iter().position(|node| node == id));
+    }
+    components.sort_by_key(|component| nodes.iter().position(|node| *node == component[0]));
+
+    // A -> B is left recursive when B can get back to A through left corners alone
+    let left_recursive = edges.iter()
+        .enumerate()
+        .filter(|(_, edge)| edge.left_corner && reach(&edges, &[edge.to], |e| e.left_corner).contains(&edge.from))
+        .map(|(index, _)| index)
+        .collect();
+
+    DependencyGraph {
+        nodes: nodes,
+        edges: edges,
+        reachable: reachable,
+        productive: productive,
+        components: components,
+        left_recursive: left_recursive,
+    }
+}
+
+fn node_line(grammer: &Grammer, graph: &DependencyGraph, id: SymbolId) -> String {
+    let mut attributes = Vec::new();
+    if ! graph.reachable.contains(&id) {
+        attributes.push("style=dashed, fontcolor=gray");
+    }
+    if ! graph.productive.contains(&id) {
+        attributes.push("color=orange, penwidth=2");
+    }
+    if attributes.is_empty() {
+        format!("\"{}\";", dot_label(grammer.name(id)))
+    } else {
+        format!("\"{}\" [{}];", dot_label(grammer.name(id)), attributes.join(", "))
+    }
+}
+
+/// Nonterminals that depend on each other are drawn in a box together. Left recursive edges are
+/// red, unreachable nonterminals dashed, and unproductive ones outlined in orange.
+pub fn print_dot_graph(grammer: &Grammer) {
+    let graph = dependency_graph(grammer);
+    println!("digraph Grammer {{");
+    println!("    label=\"red: left recursive, dashed: unreachable, orange: unproductive\";");
+
+    for (index, component) in graph.components.iter().enumerate() {
+        println!("    subgraph cluster_{} {{", index);
+        println!("        style=rounded;");
+        for id in component {
+            println!("        {}", node_line(grammer, &graph, *id));
+        }
+        println!("    }}");
+    }
+    for id in &graph.nodes {
+        if ! graph.components.iter().any(|component| component.contains(id)) {
+            println!("    {}", node_line(grammer, &graph, *id));
+        }
+    }
+
+    for (index, edge) in graph.edges.iter().enumerate() {
+        let attributes = if graph.left_recursive.contains(&index) { " [color=red, penwidth=2]" } else { "" };
+        println!(
+            "    \"{}\" -> \"{}\"{};",
+            dot_label(grammer.name(edge.from)), dot_label(grammer.name(edge.to)), attributes
+        );
+    }
+    println!("}}");
+}
@@ -0,0 +1,214 @@
+use alphabet::{SymbolId, SymbolType};
+use grammer::Grammer;
+use graph::dependency_graph;
+use lookahead::ll_1_table;
+use railroad::escape;
+use sets::{entry_name, first_sets, follow_sets, nullable_set, SetEntry};
+
+static STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; }
+pre, td, th { font-family: monospace; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+td, th { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; vertical-align: top; }
+th { background: #f4f4f4; }
+td.conflict { background: #fdd; }
+a { text-decoration: none; }
+:target { background: #ffa; }
+";
+
+// Names can have anything in them once they are literals, so anchors spell out everything else
+fn anchor(name: &str) -> String {
+    let mut result = String::from("symbol-");
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            result.push(c);
+        } else {
+            result.push_str(&format!("_{:x}_", c as u32));
+        }
+    }
+    result
+}
+
+fn symbol_link(grammer: &Grammer, id: SymbolId) -> String {
+    let name = grammer.name(id);
+    format!("<a href=\"#{}\">{}</a>", anchor(name), escape(name))
+}
+
+fn rule_link(rule: usize) -> String {
+    format!("<a href=\"#rule-{}\">{}</a>", rule, rule)
+}
+
+fn entry_link(grammer: &Grammer, entry: &SetEntry) -> String {
+    match *entry {
+        SetEntry::Id(id) => symbol_link(grammer, id),
+        _ => entry_name(grammer, entry),
+    }
+}
+
+fn links(items: Vec<String>) -> String {
+    if items.is_empty() {
+        String::from("&nbsp;")
+    } else {
+        items.join(" ")
+    }
+}
+
+fn rules_section(grammer: &Grammer, out: &mut String) {
+    out.push_str("<h2 id=\"rules\">Rules</h2>\n<table>\n<tr><th>#</th><th>Rule</th></tr>\n");
+    for (index, rule) in grammer.rules.iter().enumerate() {
+        let alternate = if rule.alternate.is_empty() {
+            String::from("Empty")
+        } else {
+            rule.alternate.iter()
+                .map(|id| symbol_link(grammer, *id))
+                .collect::<Vec<String>>()
+                .join(" ")
+        };
+        out.push_str(&format!(
+            "<tr id=\"rule-{}\"><td>{}</td><td>{} -&gt; {}.</td></tr>\n",
+            index, index, symbol_link(grammer, rule.head), alternate
+        ));
+    }
+    out.push_str("</table>\n");
+}
+
+// Each symbol with the rules it heads and the rules that use it
+fn symbols_section(grammer: &Grammer, out: &mut String) {
+    out.push_str("<h2 id=\"symbols\">Symbols</h2>\n<table>\n");
+    out.push_str("<tr><th>Symbol</th><th>Kind</th><th>Rules</th><th>Used in</th></tr>\n");
+    let symbols = grammer.alphabet.get_type_set(SymbolType::NonTerminal).iter()
+        .map(|id| (*id, "nonterminal"))
+        .chain(grammer.alphabet.get_type_set(SymbolType::Terminal).iter().map(|id| (*id, "terminal")));
+    for (id, kind) in symbols {
+        let name = grammer.name(id);
+        let kind = if id == grammer.start { "start" } else { kind };
+        let heads = grammer.rules.iter().enumerate()
+            .filter(|(_, rule)| rule.head == id)
+            .map(|(index, _)| rule_link(index))
+            .collect();
+        let uses = grammer.rules.iter().enumerate()
+            .filter(|(_, rule)| rule.alternate.contains(&id))
+            .map(|(index, _)| rule_link(index))
+            .collect();
+        out.push_str(&format!(
+            "<tr id=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            anchor(name), escape(name), kind, links(heads), links(uses)
+        ));
+    }
+    out.push_str("</table>\n");
+}
+
+fn sets_section(grammer: &Grammer, out: &mut String) {
+    let first_sets = first_sets(grammer);
+    let follow_sets = follow_sets(grammer, &first_sets);
+    let nullable = nullable_set(&first_sets);
+
+    out.push_str("<h2 id=\"sets\">Nullable, First and Follow</h2>\n<table>\n");
+    out.push_str("<tr><th>Nonterminal</th><th>Nullable</th><th>First</th><th>Follow</th></tr>\n");
+    for &id in grammer.alphabet.get_type_set(SymbolType::NonTerminal) {
+        let first = first_sets[&id].iter()
+            .map(|entry| entry_link(grammer, entry))
+            .collect();
+        let follow = follow_sets[&id].iter()
+            .map(|entry| entry_link(grammer, entry))
+            .collect();
+        out.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            symbol_link(grammer, id),
+            if nullable.contains(&id) { "yes" } else { "no" },
+            links(first),
+            links(follow)
+        ));
+    }
+    out.push_str("</table>\n");
+}
+
+// A row for each nonterminal and a column for each terminal, with cells of more than one rule
+// highlighted as conflicts
+fn ll_section(grammer: &Grammer, out: &mut String) {
+    let first_sets = first_sets(grammer);
+    let follow_sets = follow_sets(grammer, &first_sets);
+    let table = ll_1_table(grammer, &first_sets, &follow_sets);
+
+    let mut columns: Vec<SetEntry> = grammer.alphabet.get_type_set(SymbolType::Terminal).iter()
+        .cloned()
+        .map(SetEntry::Id)
+        .collect();
+    columns.push(SetEntry::End);
+
+    let conflict_count = table.conflict_count();
+    out.push_str("<h2 id=\"ll\">LL(1) table</h2>\n");
+    if conflict_count == 0 {
+        out.push_str("<p>The grammer is LL(1).</p>\n");
+    } else {
+        out.push_str(&format!("<p>There are {} conflicts.</p>\n", conflict_count));
+    }
+
+    out.push_str("<table>\n<tr><th></th>");
+    for column in &columns {
+        out.push_str(&format!("<th>{}</th>", entry_link(grammer, column)));
+    }
+    out.push_str("</tr>\n");
+    for &id in grammer.alphabet.get_type_set(SymbolType::NonTerminal) {
+        out.push_str(&format!("<tr><th>{}</th>", symbol_link(grammer, id)));
+        for column in &columns {
+            let rules = table.rules(id, *column);
+            if rules.is_empty() {
+                out.push_str("<td></td>");
+                continue;
+            }
+            let class = if rules.len() > 1 { " class=\"conflict\"" } else { "" };
+            let rules = rules.iter().map(|rule| rule_link(*rule)).collect();
+            out.push_str(&format!("<td{}>{}</td>", class, links(rules)));
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n");
+}
+
+// Nonterminals the start symbol never gets to, nonterminals that never finish deriving, and
+// terminals no rule mentions
+fn useless_section(grammer: &Grammer, out: &mut String) {
+    let graph = dependency_graph(grammer);
+    let unreachable: Vec<String> = graph.nodes.iter()
+        .filter(|id| ! graph.reachable.contains(id))
+        .map(|id| symbol_link(grammer, *id))
+        .collect();
+    let unproductive: Vec<String> = graph.nodes.iter()
+        .filter(|id| ! graph.productive.contains(id))
+        .map(|id| symbol_link(grammer, *id))
+        .collect();
+    let unused: Vec<String> = grammer.alphabet.get_type_set(SymbolType::Terminal).iter()
+        .filter(|id| ! grammer.rules.iter().any(|rule| rule.alternate.contains(id)))
+        .map(|id| symbol_link(grammer, *id))
+        .collect();
+
+    out.push_str("<h2 id=\"useless\">Useless symbols</h2>\n<table>\n");
+    let rows = [("Unreachable", unreachable), ("Unproductive", unproductive), ("Unused terminals", unused)];
+    for (label, symbols) in rows {
+        out.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>\n", label, links(symbols)));
+    }
+    out.push_str("</table>\n");
+}
+
+/// A page that stands on its own, with the grammer as written and everything worked out from it.
+/// The text is the grammer file before its includes were pulled in, as the pretty printer puts it.
+pub fn html_report(text: &str, grammer: &Grammer) -> String {
+    let mut out = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str("<title>Grammer report</title>\n");
+    out.push_str(&format!("<style>{}</style>\n", STYLE));
+    out.push_str("</head>\n<body>\n<h1>Grammer report</h1>\n");
+    out.push_str("<p><a href=\"#grammer\">Grammer</a> | <a href=\"#rules\">Rules</a> | <a href=\"#symbols\">Symbols</a> | ");
+    out.push_str("<a href=\"#sets\">Sets</a> | <a href=\"#ll\">LL(1) table</a> | <a href=\"#useless\">Useless symbols</a></p>\n");
+
+    out.push_str("<h2 id=\"grammer\">Grammer</h2>\n");
+    out.push_str(&format!("<pre>{}</pre>\n", escape(text)));
+    rules_section(grammer, &mut out);
+    symbols_section(grammer, &mut out);
+    sets_section(grammer, &mut out);
+    ll_section(grammer, &mut out);
+    useless_section(grammer, &mut out);
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use alphabet::{EMPTY_NAME, is_literal_name};
+use grammer::Diagnostic;
+use parser;
+use raw_grammer::{RawGrammer, RawName, RawSymbol, SourceFile};
+
+struct Loader {
+    // The files being included right now, outermost first, and the prefix each was included with
+    stack: Vec<(PathBuf, Option<String>)>,
+    // A file is only merged once for each full chain of prefixes it is included under
+    merged: HashSet<(PathBuf, Vec<String>)>,
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn read_file(path: &Path) -> Option<Vec<u8>> {
+    let mut buffer = Vec::new();
+    File::open(path).ok()?
+        .read_to_end(&mut buffer).ok()?;
+    Some(buffer)
+}
+
+// Literals and Empty mean the same thing in every file, so they are never prefixed
+fn prefixed(prefix: &str, name: &str) -> String {
+    if is_literal_name(name) || name == EMPTY_NAME {
+        String::from(name)
+    } else {
+        format!("{}_{}", prefix, name)
+    }
+}
+
+fn prefix_alternate(prefix: &str, alternate: &mut [RawSymbol]) {
+    for symbol in alternate {
+        match *symbol {
+            RawSymbol::Name(ref mut name) => *name = prefixed(prefix, name),
+            RawSymbol::Group(ref mut alternates)
+            | RawSymbol::Optional(ref mut alternates)
+            | RawSymbol::Repeat(ref mut alternates)
+            | RawSymbol::RepeatOne(ref mut alternates) => {
+                for inner in alternates {
+                    prefix_alternate(prefix, inner);
+                }
+            }
+        }
+    }
+}
+
+// The declarations and rules of an included grammer go after those of the one including it,
+// so its precedence levels bind tighter. The start symbol of an included grammer is not used
+fn merge(raw_grammer: &mut RawGrammer, mut included: RawGrammer, prefix: Option<&String>) {
+    if let Some(prefix) = prefix {
+        for name in included.terminals.iter_mut().chain(included.non_terminals.iter_mut()) {
+            name.name = prefixed(prefix, &name.name);
+        }
+        for rule in &mut included.rules {
+            rule.head = prefixed(prefix, &rule.head);
+            prefix_alternate(prefix, &mut rule.alternate);
+            rule.precedence = rule.precedence.as_ref().map(|name| prefixed(prefix, name));
+        }
+        for level in &mut included.precedence {
+            for name in &mut level.names {
+                name.name = prefixed(prefix, &name.name);
+            }
+        }
+        for token in &mut included.tokens {
+            token.name = prefixed(prefix, &token.name);
+        }
+    }
+
+    let file_offset = raw_grammer.files.len();
+    for span in included.spans_mut() {
+        span.file += file_offset;
+    }
+
+    raw_grammer.terminals.extend(included.terminals);
+    raw_grammer.non_terminals.extend(included.non_terminals);
+    raw_grammer.rules.extend(included.rules);
+    raw_grammer.precedence.extend(included.precedence);
+    raw_grammer.tokens.extend(included.tokens);
+    raw_grammer.skips.extend(included.skips);
+    raw_grammer.files.extend(included.files);
+}
+
+impl Loader {
+    fn prefix_chain(&self) -> Vec<String> {
+        self.stack.iter()
+            .filter_map(|(_, prefix)| prefix.clone())
+            .collect()
+    }
+
+    fn resolve(&mut self, mut raw_grammer: RawGrammer, path: &Path) -> Result<RawGrammer, Vec<Diagnostic>> {
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+
+        for include in raw_grammer.includes.clone() {
+            let location = raw_grammer.location(include.span);
+            let include_path = directory.join(&include.path);
+            let canonical_path = canonical(&include_path);
+
+            if let Some(index) = self.stack.iter().position(|(p, _)| *p == canonical_path) {
+                let cycle: Vec<String> = self.stack[index..].iter()
+                    .map(|(p, _)| p.display().to_string())
+                    .chain(Some(canonical_path.display().to_string()))
+                    .collect();
+                let message = format!("including {} forms a cycle, {}", include.path, cycle.join(" -> "));
+                return Err(vec![Diagnostic { location: Some(location), message: message }]);
+            }
+
+            let mut prefix_chain = self.prefix_chain();
+            prefix_chain.extend(include.prefix.clone());
+            if ! self.merged.insert((canonical_path.clone(), prefix_chain)) {
+                continue;
+            }
+
+            let text = match read_file(&include_path) {
+                Some(text) => text,
+                None => {
+                    let message = format!("could not read the included file {}", include_path.display());
+                    return Err(vec![Diagnostic { location: Some(location), message: message }]);
+                }
+            };
+
+            let mut included = match parser::parse(&text) {
+                Ok(included) => included,
+                Err(mut diagnostics) => {
+                    let message = format!("could not parse the included file {}", include_path.display());
+                    diagnostics.push(Diagnostic { location: Some(location), message: message });
+                    return Err(diagnostics);
+                }
+            };
+            included.files = vec![SourceFile { path: include_path.display().to_string(), text: text }];
+
+            self.stack.push((canonical_path, include.prefix.clone()));
+            let included = self.resolve(included, &include_path)?;
+            self.stack.pop();
+
+            merge(&mut raw_grammer, included, include.prefix.as_ref());
+        }
+
+        Ok(raw_grammer)
+    }
+}
+
+// Pull every :Include: of a parsed grammer into it, relative to the directory of its file.
+// The start symbol is settled first, so that it defaults to the first rule of this file
+pub(crate) fn load(mut raw_grammer: RawGrammer, path: &str, text: &[u8]) -> Result<RawGrammer, Vec<Diagnostic>> {
+    raw_grammer.files = vec![SourceFile { path: String::from(path), text: text.to_vec() }];
+    if raw_grammer.starts.is_empty() {
+        let first_head = raw_grammer.rules.first().map(|rule| RawName { name: rule.head.clone(), span: rule.span });
+        raw_grammer.starts.extend(first_head);
+    }
+
+    let mut loader = Loader {
+        stack: vec![(canonical(Path::new(path)), None)],
+        merged: HashSet::new(),
+    };
+    loader.resolve(raw_grammer, Path::new(path))
+}
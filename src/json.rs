@@ -0,0 +1,148 @@
+//! A JSON report of the sets and conflicts of a grammer.
+//!
+//! The reports use names rather than ids, so they can be read without the alphabet. Lists of
+//! symbols are in declaration order, like everything else that gets printed.
+
+use std::collections::BTreeSet;
+use alphabet::SymbolId;
+use grammer::Grammer;
+use lookahead::{first_k_sets, follow_k_sets, ll_k_conflicts};
+use lr::{action_to_string, build_automaton, build_slr_table};
+use serde_json;
+use sets::{entry_name, first_sets, follow_sets, nullable_set, SetEntry, SetMap};
+
+/// Two alternates of the same head that share lookahead strings.
+#[derive(Serialize)]
+pub struct JsonLlConflict {
+    /// The nonterminal both rules expand.
+    pub head: String,
+    /// The two rules, as written.
+    pub rules: (String, String),
+    /// A lookahead shorter than k ends at the end of the input, and an empty one is `["End"]`.
+    pub lookaheads: Vec<Vec<String>>,
+}
+
+/// A cell of the SLR(1) table with more than one action.
+#[derive(Serialize)]
+pub struct LrConflict {
+    /// The state of the automaton.
+    pub state: usize,
+    /// The terminal, or End.
+    pub lookahead: String,
+    /// The action the table keeps.
+    pub chosen: String,
+    /// The other actions.
+    pub discarded: Vec<String>,
+    /// How precedence settled it, or None when nothing did.
+    pub resolved_by: Option<String>,
+}
+
+/// The First or Follow set of one symbol.
+#[derive(Serialize)]
+pub struct NamedSet {
+    /// The symbol.
+    pub symbol: String,
+    /// Its entries.
+    pub set: Vec<String>,
+}
+
+/// Everything the JSON output holds.
+#[derive(Serialize)]
+pub struct Report {
+    /// The First set of each symbol.
+    pub first: Vec<NamedSet>,
+    /// The Follow set of each nonterminal.
+    pub follow: Vec<NamedSet>,
+    /// The nonterminals that can derive the empty string.
+    pub nullable: Vec<String>,
+    /// The lookahead the LL conflicts were found with.
+    pub k: usize,
+    /// The strong LL(k) conflicts.
+    pub ll_conflicts: Vec<JsonLlConflict>,
+    /// The SLR(1) conflicts precedence did not settle.
+    pub lr_conflicts: Vec<LrConflict>,
+    /// The SLR(1) conflicts precedence settled.
+    pub lr_resolved: Vec<LrConflict>,
+}
+
+fn entry_names(grammer: &Grammer, entries: &BTreeSet<SetEntry>) -> Vec<String> {
+    entries.iter().map(|entry| entry_name(grammer, entry)).collect()
+}
+
+fn lookahead_names(grammer: &Grammer, sequence: &[SymbolId]) -> Vec<String> {
+    if sequence.is_empty() {
+        return vec![String::from("End")];
+    }
+    sequence.iter().map(|id| String::from(grammer.name(*id))).collect()
+}
+
+// A list rather than a map, so the symbols stay in declaration order instead of being sorted by name
+fn named_sets(grammer: &Grammer, set_map: &SetMap) -> Vec<NamedSet> {
+    set_map.iter()
+        .map(|(id, entries)| NamedSet {
+            symbol: String::from(grammer.name(*id)),
+            set: entry_names(grammer, entries),
+        })
+        .collect()
+}
+
+/// The First, Follow and nullable sets, the LL(k) conflicts and those of the SLR(1) table.
+pub fn report(grammer: &Grammer, k: usize) -> Report {
+    let first_sets = first_sets(grammer);
+    let follow_sets = follow_sets(grammer, &first_sets);
+    let nullable = nullable_set(&first_sets).iter()
+        .map(|id| String::from(grammer.name(*id)))
+        .collect();
+
+    let first_k = first_k_sets(grammer, k);
+    let follow_k = follow_k_sets(grammer, &first_k, k);
+    let ll_conflicts = ll_k_conflicts(grammer, &first_k, &follow_k, k).iter()
+        .map(|conflict| JsonLlConflict {
+            head: String::from(grammer.name(conflict.head)),
+            rules: (
+                grammer.rule_to_string(&grammer.rules[conflict.rules.0]),
+                grammer.rule_to_string(&grammer.rules[conflict.rules.1])
+            ),
+            lookaheads: conflict.lookaheads.iter()
+                .map(|sequence| lookahead_names(grammer, sequence))
+                .collect(),
+        })
+        .collect();
+
+    let automaton = build_automaton(grammer);
+    let table = build_slr_table(grammer, &automaton);
+    let (mut lr_conflicts, mut lr_resolved) = (Vec::new(), Vec::new());
+    for resolution in &table.resolutions {
+        let conflict = LrConflict {
+            state: resolution.state,
+            lookahead: entry_name(grammer, &resolution.lookahead),
+            chosen: action_to_string(grammer, &resolution.chosen),
+            discarded: resolution.discarded.iter()
+                .map(|action| action_to_string(grammer, action))
+                .collect(),
+            resolved_by: resolution.reason.clone(),
+        };
+        if conflict.resolved_by.is_some() {
+            lr_resolved.push(conflict);
+        } else {
+            lr_conflicts.push(conflict);
+        }
+    }
+
+    Report {
+        first: named_sets(grammer, &first_sets),
+        follow: named_sets(grammer, &follow_sets),
+        nullable: nullable,
+        k: k,
+        ll_conflicts: ll_conflicts,
+        lr_conflicts: lr_conflicts,
+        lr_resolved: lr_resolved,
+    }
+}
+
+/// Prints the report as pretty printed JSON.
+pub fn print_report(grammer: &Grammer, k: usize) {
+    let json = serde_json::to_string_pretty(&report(grammer, k))
+        .expect("A report is only strings, numbers and lists");
+    println!("{}", json);
+}
@@ -0,0 +1,124 @@
+use regex::Regex;
+use alphabet::{is_literal_name, literal_text, SymbolId, SymbolType};
+use grammer::{Diagnostic, Grammer};
+use raw_grammer::{Span, TokenPattern};
+
+/// A terminal found in the text, and the bytes of the text it was found at.
+pub struct Token {
+    /// The terminal.
+    pub id: SymbolId,
+    /// Where the token starts in the text, in bytes.
+    pub start: usize,
+    /// Where the token ends in the text, in bytes.
+    pub end: usize,
+}
+
+enum Matcher {
+    Regex(Regex),
+    Literal(String),
+}
+
+impl Matcher {
+    // Regexes were checked with the grammer, so they compile. They are anchored to match only
+    // at the front of the text
+    fn new(pattern: &TokenPattern) -> Matcher {
+        match *pattern {
+            TokenPattern::Regex(ref text) => {
+                let regex = Regex::new(&format!("^(?:{})", text))
+                    .expect("Token regexes are checked with the grammer");
+                Matcher::Regex(regex)
+            },
+            TokenPattern::Literal(ref name) => Matcher::Literal(literal_text(name)),
+        }
+    }
+
+    fn match_length(&self, text: &str) -> Option<usize> {
+        let length = match *self {
+            Matcher::Regex(ref regex) => regex.find(text).map(|found| found.end()),
+            Matcher::Literal(ref literal) if text.starts_with(literal.as_str()) => Some(literal.len()),
+            Matcher::Literal(_) => None,
+        };
+        length.filter(|length| *length > 0)
+    }
+
+    fn is_literal(&self) -> bool {
+        matches!(*self, Matcher::Literal(_))
+    }
+}
+
+/// Splits text into the tokens of a grammer.
+pub struct Lexer {
+    // A skip is kept with no terminal, and is dropped from the tokens once matched
+    matchers: Vec<(Option<SymbolId>, Matcher)>,
+}
+
+/// Every token of the :Tokens: section in the order given, then every quoted literal of the rules
+/// that wasn't given a pattern already in the order they are first used, and last the skips.
+pub fn build_lexer(grammer: &Grammer) -> Lexer {
+    let mut matchers: Vec<(Option<SymbolId>, Matcher)> = grammer.tokens.iter()
+        .map(|(id, pattern)| (Some(*id), Matcher::new(pattern)))
+        .collect();
+
+    let literals = grammer.alphabet.get_type_set(SymbolType::Terminal).iter()
+        .filter(|id| ! grammer.tokens.iter().any(|(token, _)| token == *id))
+        .map(|id| (grammer.alphabet.name_for_id(*id).expect("Every terminal has a name"), *id))
+        .filter(|(name, _)| is_literal_name(name));
+    for (name, id) in literals {
+        matchers.push((Some(id), Matcher::Literal(literal_text(name))));
+    }
+
+    for skip in &grammer.skips {
+        matchers.push((None, Matcher::new(skip)));
+    }
+    Lexer { matchers: matchers }
+}
+
+impl Lexer {
+    /// The tokens of the text, or where some part of it matches no pattern. The longest
+    /// match wins. Of matches the same length a literal beats a regex, so keywords win over
+    /// identifiers, and otherwise the one that comes first wins.
+    pub fn lex(&self, text: &str) -> Result<Vec<Token>, Vec<Diagnostic>> {
+        let mut tokens = Vec::new();
+        let mut position = 0;
+        while position < text.len() {
+            let rest = &text[position..];
+            let mut best: Option<(usize, bool, Option<SymbolId>)> = None;
+            for (id, matcher) in &self.matchers {
+                if let Some(length) = matcher.match_length(rest) {
+                    let better = match best {
+                        Some((best_length, best_literal, _)) => {
+                            length > best_length || (length == best_length && matcher.is_literal() && ! best_literal)
+                        },
+                        None => true,
+                    };
+                    if better {
+                        best = Some((length, matcher.is_literal(), *id));
+                    }
+                }
+            }
+
+            match best {
+                Some((length, _, id)) => {
+                    if let Some(id) = id {
+                        tokens.push(Token { id: id, start: position, end: position + length });
+                    }
+                    position += length;
+                },
+                None => {
+                    let (line, col) = Span { start: position, end: position, file: 0 }.line_col(text.as_bytes());
+                    let found: String = rest.chars()
+                        .enumerate()
+                        .take_while(|(index, c)| *index == 0 || ! c.is_whitespace())
+                        .map(|(_, c)| c)
+                        .take(20)
+                        .collect();
+                    return Err(vec![Diagnostic {
+                        location: Some(format!("line {}, column {}", line, col)),
+                        message: format!("no token matches the text starting with '{}'", found),
+                    }]);
+                }
+            }
+        }
+        Ok(tokens)
+    }
+}
@@ -0,0 +1,109 @@
+//! Reading, checking and analysing context free grammers written in the `.grammer` format.
+//!
+//! A grammer is first parsed into a `RawGrammer`, which is just what the text said, and then
+//! checked into a `Grammer`, where every symbol has a `SymbolId` and a `SymbolType`. The
+//! analyses all work on a checked `Grammer`.
+//!
+//! ```
+//! use grammer_sets::{check, parse, first_sets, follow_sets, nullable_set, SetEntry};
+//!
+//! let raw = parse(b":Rules: E -> E '+' T | T. T -> Id. T -> Empty.").unwrap();
+//! let grammer = check(&raw, true).unwrap();
+//!
+//! let first = first_sets(&grammer);
+//! let follow = follow_sets(&grammer, &first);
+//! let e = grammer.alphabet.id_for_name("E").unwrap();
+//! assert!(nullable_set(&first).contains(&e));
+//! assert!(follow[&e].contains(&SetEntry::End));
+//! ```
+//!
+//! Grammers can also be put together in code with a `GrammarBuilder`. Loading, parsing and
+//! checking return a `Diagnostic` for each problem found, and leave printing them to the caller.
+//!
+//! Everything is at the top of the crate: the analyses that return sets and tables, and the
+//! reports that print or render them. With the `serde` feature, `json` has the report that
+//! `--format json` prints.
+
+#![deny(missing_docs)]
+#![allow(clippy::redundant_field_names)]
+
+#[macro_use]extern crate nom;
+extern crate regex;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+#[macro_use]extern crate serde_derive;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+
+mod alphabet;
+mod builder;
+mod derivation;
+mod diff;
+mod earley;
+mod ebnf;
+mod explain;
+mod grammer;
+mod graph;
+mod html;
+mod include;
+#[cfg(feature = "serde")]
+pub mod json;
+mod lexer;
+mod lookahead;
+mod lr;
+mod parser;
+mod printer;
+mod railroad;
+mod raw_grammer;
+mod sets;
+mod tables;
+
+use std::fs::File;
+use std::io::Read;
+
+pub use alphabet::{SymbolId, SymbolType, TypedAlphabet};
+pub use builder::GrammarBuilder;
+pub use derivation::{print_ascii_tree, print_derivations, print_dot_tree};
+pub use diff::print_diff;
+pub use earley::{parse_tokens, tokens_for_sentence, ParseTree};
+pub use explain::{why_first, why_follow};
+pub use grammer::{Diagnostic, Grammer, Grammer as Grammar, Precedence, Rule};
+pub use graph::print_dot_graph;
+pub use html::html_report;
+pub use lexer::{build_lexer, Lexer, Token};
+pub use lookahead::{first_k_sets, follow_k_sets, ll_1_table, ll_k_conflicts, print_conflicts};
+pub use lookahead::{print_first_k_sets, print_follow_k_sets, LlConflict, LlTable, TerminalTrie, TrieMap};
+pub use lr::{build_automaton, build_slr_table, print_dot_automaton, print_table_report, Automaton, Table};
+pub use printer::{format_raw_grammer, format_text};
+pub use railroad::railroad_svg;
+pub use raw_grammer::{Associativity, RawGrammer, RawGrammer as RawGrammar, TokenPattern};
+pub use sets::{first_sets, follow_sets, nullable_set, print_sets, trace_first_sets, trace_follow_sets, SetEntry, SetMap};
+pub use tables::{ll_table, print_tables, rules_table, sets_table, slr_table, TableFormat};
+
+/// Parse the text of a grammer. Its `:Include:` sections are kept but not followed, see `load`.
+pub fn parse(input: &[u8]) -> Result<RawGrammer, Vec<Diagnostic>> {
+    parser::parse(input)
+}
+
+/// Read and parse a grammer file, pulling in everything it includes.
+pub fn load(path: &str) -> Result<RawGrammer, Vec<Diagnostic>> {
+    let mut text = Vec::new();
+    if File::open(path).and_then(|mut file| file.read_to_end(&mut text)).is_err() {
+        return Err(vec![Diagnostic { location: None, message: format!("could not read {}", path) }]);
+    }
+    let raw_grammer = parse(&text)?;
+    load_includes(raw_grammer, path, &text)
+}
+
+/// Pull everything a parsed grammer includes into it. The path and text are those of the file
+/// it was parsed from, included paths are relative to its directory.
+pub fn load_includes(raw_grammer: RawGrammer, path: &str, text: &[u8]) -> Result<RawGrammer, Vec<Diagnostic>> {
+    include::load(raw_grammer, path, text)
+}
+
+/// Check a parsed grammer, returning everything wrong with it if there is anything. With
+/// `infer_terminals`, undeclared symbols that never head a rule are taken to be terminals.
+pub fn check(raw_grammer: &RawGrammer, infer_terminals: bool) -> Result<Grammer, Vec<Diagnostic>> {
+    grammer::check(raw_grammer, infer_terminals)
+}
@@ -0,0 +1,379 @@
+use std::collections::{BTreeMap, BTreeSet};
+use alphabet::{EMPTY_NAME, SymbolId, SymbolType};
+use grammer::Grammer;
+use sets::{print_sets, sequence_first_set, SetEntry, SetMap};
+
+/// A set of terminal strings, each at most k long, stored as a trie. The empty string
+/// is in the set when the root is marked as an end. Strings come out in declaration order.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct TerminalTrie {
+    children: BTreeMap<SymbolId, TerminalTrie>,
+    end: bool,
+}
+
+impl TerminalTrie {
+    pub(crate) fn new() -> TerminalTrie {
+        TerminalTrie::default()
+    }
+
+    fn empty_string() -> TerminalTrie {
+        let mut trie = TerminalTrie::new();
+        trie.end = true;
+        trie
+    }
+
+    pub(crate) fn insert(&mut self, sequence: &[SymbolId]) -> bool {
+        match sequence.split_first() {
+            None => {
+                let inserted_something_new = ! self.end;
+                self.end = true;
+                inserted_something_new
+            },
+            Some((first, rest)) => {
+                let child_is_new = ! self.children.contains_key(first);
+                self.children.entry(*first)
+                    .or_default()
+                    .insert(rest) || child_is_new
+            }
+        }
+    }
+
+    /// Whether the set holds exactly this string.
+    pub fn contains(&self, sequence: &[SymbolId]) -> bool {
+        match sequence.split_first() {
+            None => self.end,
+            Some((first, rest)) => self.children.get(first)
+                .is_some_and(|child| child.contains(rest)),
+        }
+    }
+
+    /// Whether the set holds the empty string.
+    pub fn contains_empty_string(&self) -> bool {
+        self.end
+    }
+
+    /// Whether the set holds no strings at all, not even the empty one.
+    pub fn is_empty(&self) -> bool {
+        ! self.end && self.children.is_empty()
+    }
+
+    pub(crate) fn union(&mut self, other: &TerminalTrie) -> bool {
+        let mut inserted_something_new = false;
+        if other.end && ! self.end {
+            self.end = true;
+            inserted_something_new = true;
+        }
+        for (id, other_child) in &other.children {
+            let child_is_new = ! self.children.contains_key(id);
+            inserted_something_new = self.children.entry(*id)
+                .or_default()
+                .union(other_child) || child_is_new || inserted_something_new;
+        }
+        inserted_something_new
+    }
+
+    /// Every string in the set, shortest prefixes first.
+    pub fn sequences(&self) -> Vec<Vec<SymbolId>> {
+        let mut result = Vec::new();
+        if self.end {
+            result.push(Vec::new());
+        }
+        for (id, child) in &self.children {
+            for mut sequence in child.sequences() {
+                sequence.insert(0, *id);
+                result.push(sequence);
+            }
+        }
+        result
+    }
+
+    // Every string of self followed by every string of other, cut off after k terminals
+    pub(crate) fn concat(&self, other: &TerminalTrie, k: usize) -> TerminalTrie {
+        let mut result = TerminalTrie::new();
+        for prefix in self.sequences() {
+            if prefix.len() >= k {
+                result.insert(&prefix[..k]);
+                continue;
+            }
+            for suffix in other.sequences() {
+                let mut sequence = prefix.clone();
+                sequence.extend(suffix);
+                sequence.truncate(k);
+                result.insert(&sequence);
+            }
+        }
+        result
+    }
+
+    pub(crate) fn intersection(&self, other: &TerminalTrie) -> Vec<Vec<SymbolId>> {
+        self.sequences().into_iter()
+            .filter(|sequence| other.contains(sequence))
+            .collect()
+    }
+
+    // For k = 1 every string is a single terminal or empty, which is what SetEntry describes.
+    // The empty string is Empty in a first set, and End in a follow set
+    pub(crate) fn to_set_entries(&self, empty_entry: SetEntry) -> BTreeSet<SetEntry> {
+        self.sequences().iter()
+            .map(|sequence| match sequence.first() {
+                Some(id) => SetEntry::Id(*id),
+                None => empty_entry,
+            })
+            .collect()
+    }
+}
+
+/// The k lookahead strings of each symbol.
+pub type TrieMap = BTreeMap<SymbolId, TerminalTrie>;
+
+pub(crate) fn sequence_first_k(sequence: &[SymbolId], first_k: &TrieMap, k: usize) -> TerminalTrie {
+    let mut result = TerminalTrie::empty_string();
+    for id in sequence {
+        result = result.concat(&first_k[id], k);
+    }
+    result
+}
+
+/// The strings of at most k terminals that each symbol can start with.
+pub fn first_k_sets(grammer: &Grammer, k: usize) -> TrieMap {
+    let mut trie_map = BTreeMap::new();
+
+    for id in grammer.alphabet.get_type_set(SymbolType::NonTerminal) {
+        trie_map.insert(*id, TerminalTrie::new());
+    }
+
+    for id in grammer.alphabet.get_type_set(SymbolType::Terminal) {
+        let mut trie = TerminalTrie::new();
+        trie.insert(&[*id]);
+        trie_map.insert(*id, trie);
+    }
+
+    let mut need_another_pass = true;
+    while need_another_pass {
+        need_another_pass = false;
+
+        for rule in &grammer.rules {
+            let alternate_first_k = sequence_first_k(&rule.alternate, &trie_map, k);
+            need_another_pass = trie_map.get_mut(&rule.head).unwrap().union(&alternate_first_k)
+                || need_another_pass;
+        }
+    }
+
+    trie_map
+}
+
+/// The strings of at most k terminals that can follow each nonterminal. A string shorter
+/// than k means the input can end there.
+pub fn follow_k_sets(grammer: &Grammer, first_k: &TrieMap, k: usize) -> TrieMap {
+    let mut trie_map = BTreeMap::new();
+
+    for id in grammer.alphabet.get_type_set(SymbolType::NonTerminal) {
+        trie_map.insert(*id, TerminalTrie::new());
+    }
+
+    // A string shorter than k means the input ends there, so the start symbol is followed by nothing
+    trie_map.get_mut(&grammer.start).unwrap().insert(&[]);
+
+    let mut need_another_pass = true;
+    while need_another_pass {
+        need_another_pass = false;
+
+        for rule in &grammer.rules {
+            for (index, id) in rule.alternate.iter().enumerate() {
+                if ! grammer.alphabet.is_type(id, SymbolType::NonTerminal) {
+                    continue;
+                }
+
+                let rest_first_k = sequence_first_k(&rule.alternate[index + 1..], first_k, k);
+                let id_follow_k = rest_first_k.concat(&trie_map[&rule.head], k);
+                need_another_pass = trie_map.get_mut(id).unwrap().union(&id_follow_k)
+                    || need_another_pass;
+            }
+        }
+    }
+
+    trie_map
+}
+
+pub(crate) fn to_set_map(trie_map: &TrieMap, empty_entry: SetEntry) -> SetMap {
+    trie_map.iter()
+        .map(|(id, trie)| (*id, trie.to_set_entries(empty_entry)))
+        .collect()
+}
+
+fn sequence_to_string(grammer: &Grammer, sequence: &[SymbolId], empty_name: &str) -> String {
+    if sequence.is_empty() {
+        return String::from(empty_name);
+    }
+
+    sequence.iter()
+        .map(|id| grammer.name(*id))
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn print_trie_sets(label: &str, grammer: &Grammer, trie_map: &TrieMap, empty_name: &str) {
+    for (k, v) in trie_map {
+        print!("{}({}) = {{", label, grammer.name(*k));
+        for sequence in v.sequences() {
+            print!("{},", sequence_to_string(grammer, &sequence, empty_name));
+        }
+        println!("}}");
+    }
+}
+
+/// Prints the first k sets. When k is 1 these go through `SetEntry`, so the output is
+/// exactly that of `print_sets`.
+pub fn print_first_k_sets(grammer: &Grammer, first_k: &TrieMap, k: usize) {
+    if k == 1 {
+        print_sets("First", grammer, &to_set_map(first_k, SetEntry::Empty));
+    } else {
+        print_trie_sets(&format!("First{}", k), grammer, first_k, EMPTY_NAME);
+    }
+}
+
+/// Prints the follow k sets, like `print_first_k_sets`.
+pub fn print_follow_k_sets(grammer: &Grammer, follow_k: &TrieMap, k: usize) {
+    if k == 1 {
+        print_sets("Follow", grammer, &to_set_map(follow_k, SetEntry::End));
+    } else {
+        print_trie_sets(&format!("Follow{}", k), grammer, follow_k, "End");
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Two alternates of the same head that share lookahead strings.
+pub struct LlConflict {
+    /// The nonterminal both rules expand.
+    pub head: SymbolId,
+    /// The indices of the two rules.
+    pub rules: (usize, usize),
+    /// The lookahead strings both rules can start with.
+    pub lookaheads: Vec<Vec<SymbolId>>,
+}
+
+/// A grammer is strong LL(k) when the k lookahead strings of the alternates of each head are
+/// disjoint, taking the follow set of the head into account for alternates that run short.
+pub fn ll_k_conflicts(grammer: &Grammer, first_k: &TrieMap, follow_k: &TrieMap, k: usize) -> Vec<LlConflict> {
+    let lookaheads: Vec<TerminalTrie> = grammer.rules.iter()
+        .map(|rule| sequence_first_k(&rule.alternate, first_k, k).concat(&follow_k[&rule.head], k))
+        .collect();
+
+    let mut conflicts = Vec::new();
+    for (i, rule) in grammer.rules.iter().enumerate() {
+        for (j, other) in grammer.rules.iter().enumerate().skip(i + 1) {
+            if rule.head != other.head {
+                continue;
+            }
+
+            let shared = lookaheads[i].intersection(&lookaheads[j]);
+            if ! shared.is_empty() {
+                conflicts.push(LlConflict {
+                    head: rule.head,
+                    rules: (i, j),
+                    lookaheads: shared,
+                });
+            }
+        }
+    }
+    conflicts
+}
+
+// The rules for each nonterminal and lookahead
+type LlEntries = BTreeMap<(SymbolId, SetEntry), Vec<usize>>;
+
+/// Which rules to expand a nonterminal by on each lookahead, where `End` is the end of the input.
+/// The grammer is LL(1) when no entry has more than one rule.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct LlTable {
+    #[cfg_attr(feature = "serde", serde(with = "ll_entries"))]
+    entries: LlEntries,
+}
+
+// A pair can't be the key of a JSON object, so the table is written as a list of
+// {head, lookahead, rules} entries instead
+#[cfg(feature = "serde")]
+mod ll_entries {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use alphabet::SymbolId;
+    use sets::SetEntry;
+    use super::LlEntries;
+
+    #[derive(Serialize, Deserialize)]
+    struct Entry {
+        head: SymbolId,
+        lookahead: SetEntry,
+        rules: Vec<usize>,
+    }
+
+    pub fn serialize<S: Serializer>(entries: &LlEntries, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries: Vec<Entry> = entries.iter()
+            .map(|(&(head, lookahead), rules)| Entry { head: head, lookahead: lookahead, rules: rules.clone() })
+            .collect();
+        entries.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<LlEntries, D::Error> {
+        let entries: Vec<Entry> = Vec::deserialize(deserializer)?;
+        Ok(entries.into_iter().map(|entry| ((entry.head, entry.lookahead), entry.rules)).collect())
+    }
+}
+
+impl LlTable {
+    /// The rules to expand `head` by on `lookahead`, empty when there are none.
+    pub fn rules(&self, head: SymbolId, lookahead: SetEntry) -> &[usize] {
+        self.entries.get(&(head, lookahead)).map_or(&[], |rules| &rules[..])
+    }
+
+    /// How many entries have more than one rule.
+    pub fn conflict_count(&self) -> usize {
+        self.entries.values().filter(|rules| rules.len() > 1).count()
+    }
+}
+
+/// A rule goes in under every terminal of its first set, and when it can derive Empty, under
+/// everything that can follow its head as well.
+pub fn ll_1_table(grammer: &Grammer, first_sets: &SetMap, follow_sets: &SetMap) -> LlTable {
+    let mut entries: LlEntries = BTreeMap::new();
+    for (index, rule) in grammer.rules.iter().enumerate() {
+        let first = sequence_first_set(&rule.alternate, first_sets);
+        let mut lookaheads: Vec<SetEntry> = first.iter()
+            .filter(|entry| **entry != SetEntry::Empty)
+            .cloned()
+            .collect();
+        if first.contains(&SetEntry::Empty) {
+            lookaheads.extend(follow_sets[&rule.head].iter().cloned());
+        }
+
+        for lookahead in lookaheads {
+            let rules = entries.entry((rule.head, lookahead)).or_default();
+            if ! rules.contains(&index) {
+                rules.push(index);
+            }
+        }
+    }
+    LlTable { entries: entries }
+}
+
+/// Prints each LL(k) conflict with the lookaheads it happens on.
+pub fn print_conflicts(grammer: &Grammer, conflicts: &[LlConflict], k: usize) {
+    if conflicts.is_empty() {
+        println!("The grammer is strong LL({})", k);
+        return;
+    }
+
+    for conflict in conflicts {
+        println!(
+            "LL({}) conflict on {} between `{}` and `{}`",
+            k,
+            grammer.name(conflict.head),
+            grammer.rule_to_string(&grammer.rules[conflict.rules.0]),
+            grammer.rule_to_string(&grammer.rules[conflict.rules.1])
+        );
+        for sequence in &conflict.lookaheads {
+            println!("    on lookahead {}", sequence_to_string(grammer, sequence, "End"));
+        }
+    }
+}
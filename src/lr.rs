@@ -0,0 +1,412 @@
+use std::collections::{BTreeMap, HashMap};
+use alphabet::{SymbolId, SymbolType};
+use derivation::dot_label;
+use grammer::Grammer;
+use raw_grammer::Associativity;
+use sets::{entry_name, first_sets, follow_sets, SetEntry};
+
+// An LR(0) item. The rule one past the last rule of the grammer is the added rule S' -> S
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct Item {
+    pub(crate) rule: usize,
+    pub(crate) dot: usize,
+}
+
+/// The canonical collection of LR(0) item sets. Each state holds its kernel items first and
+/// then the rest of its closure, and transitions are in the order their symbols first came up.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Automaton {
+    augmented: Vec<SymbolId>,
+    pub(crate) states: Vec<Vec<Item>>,
+    pub(crate) transitions: Vec<Vec<(SymbolId, usize)>>,
+}
+
+impl Automaton {
+    pub(crate) fn alternate<'a>(&'a self, grammer: &'a Grammer, rule: usize) -> &'a [SymbolId] {
+        if rule == grammer.rules.len() {
+            &self.augmented
+        } else {
+            &grammer.rules[rule].alternate
+        }
+    }
+
+    pub(crate) fn next_symbol(&self, grammer: &Grammer, item: Item) -> Option<SymbolId> {
+        self.alternate(grammer, item.rule).get(item.dot).cloned()
+    }
+
+    fn closure(&self, grammer: &Grammer, kernel: Vec<Item>) -> Vec<Item> {
+        let mut items = kernel;
+        let mut next = 0;
+        while next < items.len() {
+            let item = items[next];
+            next += 1;
+
+            let id = match self.next_symbol(grammer, item) {
+                Some(id) if grammer.alphabet.is_type(&id, SymbolType::NonTerminal) => id,
+                _ => continue,
+            };
+            for (index, rule) in grammer.rules.iter().enumerate() {
+                let predicted = Item { rule: index, dot: 0 };
+                if rule.head == id && ! items.contains(&predicted) {
+                    items.push(predicted);
+                }
+            }
+        }
+        items
+    }
+}
+
+/// Builds the LR(0) automaton of the grammer, augmented with a new start rule.
+pub fn build_automaton(grammer: &Grammer) -> Automaton {
+    let mut automaton = Automaton {
+        augmented: vec![grammer.start],
+        states: Vec::new(),
+        transitions: Vec::new(),
+    };
+
+    // Kernels are kept sorted, so the same set of items always finds the same state
+    let start_kernel = vec![Item { rule: grammer.rules.len(), dot: 0 }];
+    let mut kernels = HashMap::new();
+    kernels.insert(start_kernel.clone(), 0);
+    let start_state = automaton.closure(grammer, start_kernel);
+    automaton.states.push(start_state);
+    automaton.transitions.push(Vec::new());
+
+    let mut next = 0;
+    while next < automaton.states.len() {
+        let mut symbols = Vec::new();
+        for item in &automaton.states[next] {
+            if let Some(id) = automaton.next_symbol(grammer, *item) {
+                if ! symbols.contains(&id) {
+                    symbols.push(id);
+                }
+            }
+        }
+
+        for id in symbols {
+            let mut kernel: Vec<Item> = automaton.states[next].iter()
+                .filter(|item| automaton.next_symbol(grammer, **item) == Some(id))
+                .map(|item| Item { dot: item.dot + 1, ..*item })
+                .collect();
+            kernel.sort();
+
+            let target = match kernels.get(&kernel) {
+                Some(target) => *target,
+                None => {
+                    let target = automaton.states.len();
+                    kernels.insert(kernel.clone(), target);
+                    let state = automaton.closure(grammer, kernel);
+                    automaton.states.push(state);
+                    automaton.transitions.push(Vec::new());
+                    target
+                }
+            };
+            automaton.transitions[next].push((id, target));
+        }
+        next += 1;
+    }
+
+    automaton
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) enum Action {
+    Shift(usize),
+    Reduce(usize),
+    Accept,
+    // Only ever chosen by %nonassoc, the two operators can't be next to each other
+    Error,
+}
+
+// When more than one action fits, which one won. The reason is there when precedence decided
+// it, otherwise this is a conflict and the action is the one yacc would pick by default
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct Resolution {
+    pub(crate) state: usize,
+    pub(crate) lookahead: SetEntry,
+    pub(crate) chosen: Action,
+    pub(crate) discarded: Vec<Action>,
+    pub(crate) reason: Option<String>,
+}
+
+/// The actions and gotos of each state of an automaton, with the conflicts that were
+/// resolved by precedence and the ones that were not.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Table {
+    #[cfg_attr(feature = "serde", serde(with = "action_pairs"))]
+    pub(crate) actions: Vec<BTreeMap<SetEntry, Action>>,
+    pub(crate) gotos: Vec<BTreeMap<SymbolId, usize>>,
+    pub(crate) resolutions: Vec<Resolution>,
+}
+
+// A SetEntry can't be the key of a JSON object, so the actions of each state are written
+// as a list of [lookahead, action] pairs instead
+#[cfg(feature = "serde")]
+mod action_pairs {
+    use std::collections::BTreeMap;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use sets::SetEntry;
+    use super::Action;
+
+    pub fn serialize<S: Serializer>(actions: &[BTreeMap<SetEntry, Action>], serializer: S) -> Result<S::Ok, S::Error> {
+        let pairs: Vec<Vec<(&SetEntry, &Action)>> = actions.iter()
+            .map(|state| state.iter().collect())
+            .collect();
+        pairs.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<BTreeMap<SetEntry, Action>>, D::Error> {
+        let pairs: Vec<Vec<(SetEntry, Action)>> = Vec::deserialize(deserializer)?;
+        Ok(pairs.into_iter().map(|state| state.into_iter().collect()).collect())
+    }
+}
+
+impl Table {
+    pub(crate) fn conflicts(&self) -> Vec<&Resolution> {
+        self.resolutions.iter()
+            .filter(|resolution| resolution.reason.is_none())
+            .collect()
+    }
+}
+
+fn associativity_name(associativity: Associativity) -> &'static str {
+    match associativity {
+        Associativity::Left => "%left",
+        Associativity::Right => "%right",
+        Associativity::NonAssoc => "%nonassoc",
+    }
+}
+
+// Shift/reduce conflicts are settled by precedence the way yacc does it. Without a precedence
+// on both sides a shift wins, and of two reduces the earlier rule wins, both as conflicts
+fn resolve(grammer: &Grammer, state: usize, lookahead: SetEntry, candidates: Vec<Action>) -> (Action, Vec<Resolution>) {
+    let mut resolutions = Vec::new();
+    let mut record = |chosen: Action, discarded: Vec<Action>, reason: Option<String>| {
+        resolutions.push(Resolution {
+            state: state,
+            lookahead: lookahead,
+            chosen: chosen,
+            discarded: discarded,
+            reason: reason,
+        });
+    };
+
+    if candidates.len() == 1 {
+        return (candidates[0], resolutions);
+    }
+
+    if candidates.contains(&Action::Accept) {
+        let discarded = candidates.into_iter().filter(|a| *a != Action::Accept).collect();
+        record(Action::Accept, discarded, None);
+        return (Action::Accept, resolutions);
+    }
+
+    let shift = candidates.iter().cloned().find(|a| matches!(*a, Action::Shift(_)));
+    let mut reduces: Vec<usize> = candidates.iter()
+        .filter_map(|a| match *a {
+            Action::Reduce(rule) => Some(rule),
+            _ => None,
+        })
+        .collect();
+    reduces.sort();
+
+    let reduce = reduces[0];
+    if reduces.len() > 1 {
+        let discarded = reduces[1..].iter().map(|rule| Action::Reduce(*rule)).collect();
+        record(Action::Reduce(reduce), discarded, None);
+    }
+
+    let shift = match shift {
+        Some(shift) => shift,
+        None => return (Action::Reduce(reduce), resolutions),
+    };
+
+    let token_precedence = match lookahead {
+        SetEntry::Id(id) => grammer.precedence.get(&id).cloned(),
+        _ => None,
+    };
+    let (token_precedence, rule_precedence) = match (token_precedence, grammer.rules[reduce].precedence) {
+        (Some(token_precedence), Some(rule_precedence)) => (token_precedence, rule_precedence),
+        _ => {
+            record(shift, vec![Action::Reduce(reduce)], None);
+            return (shift, resolutions);
+        }
+    };
+
+    let token = entry_name(grammer, &lookahead);
+    let (chosen, reason) = if rule_precedence.level > token_precedence.level {
+        (Action::Reduce(reduce), format!("the rule binds tighter than {}", token))
+    } else if rule_precedence.level < token_precedence.level {
+        (shift, format!("{} binds tighter than the rule", token))
+    } else {
+        let reason = format!("{} is {}", token, associativity_name(token_precedence.associativity));
+        match token_precedence.associativity {
+            Associativity::Left => (Action::Reduce(reduce), reason),
+            Associativity::Right => (shift, reason),
+            Associativity::NonAssoc => (Action::Error, reason),
+        }
+    };
+
+    let discarded = vec![shift, Action::Reduce(reduce)].into_iter()
+        .filter(|action| *action != chosen)
+        .collect();
+    record(chosen, discarded, Some(reason));
+    (chosen, resolutions)
+}
+
+/// An SLR(1) table, reducing on every terminal in the follow set of the head of a rule.
+pub fn build_slr_table(grammer: &Grammer, automaton: &Automaton) -> Table {
+    let first_sets = first_sets(grammer);
+    let follow_sets = follow_sets(grammer, &first_sets);
+
+    let mut table = Table {
+        actions: Vec::new(),
+        gotos: Vec::new(),
+        resolutions: Vec::new(),
+    };
+
+    for (state, items) in automaton.states.iter().enumerate() {
+        // Lookaheads are kept in the order they were first seen, for the order of the report
+        let mut candidates: Vec<(SetEntry, Vec<Action>)> = Vec::new();
+        let mut add = |lookahead: SetEntry, action: Action| {
+            match candidates.iter_mut().find(|&&mut (entry, _)| entry == lookahead) {
+                Some(&mut (_, ref mut actions)) => {
+                    if ! actions.contains(&action) {
+                        actions.push(action);
+                    }
+                },
+                None => candidates.push((lookahead, vec![action])),
+            }
+        };
+
+        let mut gotos = BTreeMap::new();
+        for &(id, target) in &automaton.transitions[state] {
+            if grammer.alphabet.is_type(&id, SymbolType::Terminal) {
+                add(SetEntry::Id(id), Action::Shift(target));
+            } else {
+                gotos.insert(id, target);
+            }
+        }
+
+        for item in items {
+            if item.dot < automaton.alternate(grammer, item.rule).len() {
+                continue;
+            }
+
+            if item.rule == grammer.rules.len() {
+                add(SetEntry::End, Action::Accept);
+                continue;
+            }
+
+            let head = grammer.rules[item.rule].head;
+            for entry in &follow_sets[&head] {
+                add(*entry, Action::Reduce(item.rule));
+            }
+        }
+
+        let mut actions = BTreeMap::new();
+        for (lookahead, state_candidates) in candidates {
+            let (action, resolutions) = resolve(grammer, state, lookahead, state_candidates);
+            actions.insert(lookahead, action);
+            table.resolutions.extend(resolutions);
+        }
+        table.actions.push(actions);
+        table.gotos.push(gotos);
+    }
+
+    table
+}
+
+pub(crate) fn action_to_string(grammer: &Grammer, action: &Action) -> String {
+    match *action {
+        Action::Shift(state) => format!("shift to state {}", state),
+        Action::Reduce(rule) => format!("reduce `{}`", grammer.rule_to_string(&grammer.rules[rule])),
+        Action::Accept => String::from("accept"),
+        Action::Error => String::from("error"),
+    }
+}
+
+/// Every resolution gets reported, so that precedence never hides a conflict silently.
+pub fn print_table_report(grammer: &Grammer, automaton: &Automaton, table: &Table) {
+    println!("The SLR(1) automaton has {} states", automaton.states.len());
+
+    for resolution in &table.resolutions {
+        let discarded: Vec<String> = resolution.discarded.iter()
+            .map(|action| action_to_string(grammer, action))
+            .collect();
+        let label = if resolution.reason.is_some() { "Resolved" } else { "Conflict" };
+        print!(
+            "{} in state {} on {}: {} over {}",
+            label,
+            resolution.state,
+            entry_name(grammer, &resolution.lookahead),
+            action_to_string(grammer, &resolution.chosen),
+            discarded.join(" and ")
+        );
+        match (resolution.reason.as_ref(), resolution.chosen) {
+            (Some(reason), _) => println!(", {}", reason),
+            (None, Action::Reduce(_)) => println!(", the earlier rule wins"),
+            (None, _) => println!(", with no precedence to decide"),
+        }
+    }
+
+    let conflicts = table.conflicts();
+    if conflicts.is_empty() {
+        println!("The grammer is SLR(1)");
+    } else {
+        println!("There were {} unresolved conflicts", conflicts.len());
+    }
+}
+
+// The added rule is written with the start symbol primed, as S' -> S
+fn item_to_string(grammer: &Grammer, automaton: &Automaton, item: Item) -> String {
+    let head = if item.rule == grammer.rules.len() {
+        format!("{}'", grammer.name(grammer.start))
+    } else {
+        String::from(grammer.name(grammer.rules[item.rule].head))
+    };
+
+    let mut result = format!("{} ->", head);
+    for (position, id) in automaton.alternate(grammer, item.rule).iter().enumerate() {
+        if position == item.dot {
+            result.push_str(" .");
+        }
+        result.push(' ');
+        result.push_str(grammer.name(*id));
+    }
+    if item.dot == automaton.alternate(grammer, item.rule).len() {
+        result.push_str(" .");
+    }
+    result
+}
+
+/// Each state is a box of its items, kernel items first. States with a conflict that precedence
+/// didn't settle are red.
+pub fn print_dot_automaton(grammer: &Grammer, automaton: &Automaton, table: &Table) {
+    let conflict_states: Vec<usize> = table.conflicts().iter()
+        .map(|resolution| resolution.state)
+        .collect();
+
+    println!("digraph Automaton {{");
+    println!("    node [shape=box];");
+    for (state, items) in automaton.states.iter().enumerate() {
+        let mut label = format!("State {}\\l", state);
+        for item in items {
+            label.push_str(&dot_label(&item_to_string(grammer, automaton, *item)));
+            label.push_str("\\l");
+        }
+        if conflict_states.contains(&state) {
+            println!("    s{} [label=\"{}\", color=red, fontcolor=red];", state, label);
+        } else {
+            println!("    s{} [label=\"{}\"];", state, label);
+        }
+    }
+    for (state, transitions) in automaton.transitions.iter().enumerate() {
+        for &(id, target) in transitions {
+            println!("    s{} -> s{} [label=\"{}\"];", state, target, dot_label(grammer.name(id)));
+        }
+    }
+    println!("}}");
+}
@@ -1,22 +1,251 @@
-#![allow(dead_code)]
-
 extern crate clap;
-#[macro_use]extern crate nom;
+extern crate grammer_sets;
 
 mod args;
-mod alphabet;
-mod parser;
-mod raw_grammer;
 
+use grammer_sets::{build_automaton, build_lexer, build_slr_table, check, first_k_sets, first_sets, follow_k_sets, follow_sets};
+use grammer_sets::{format_raw_grammer, format_text, html_report, ll_k_conflicts, load_includes, parse, parse_tokens};
+use grammer_sets::{print_ascii_tree, print_conflicts, print_derivations, print_diff, print_dot_automaton, print_dot_graph};
+use grammer_sets::{print_dot_tree, print_first_k_sets, print_follow_k_sets, print_sets, print_table_report, print_tables};
+use grammer_sets::{railroad_svg, tokens_for_sentence, trace_first_sets, trace_follow_sets, why_first, why_follow};
+use grammer_sets::{Diagnostic, Grammer, RawGrammer, TableFormat};
+use std::fs::File;
+use std::io::Write;
 use std::process::exit;
 
+#[cfg(feature = "serde")]
+fn print_json(grammer: &Grammer, k: usize) {
+    grammer_sets::json::print_report(grammer, k);
+}
+
+#[cfg(not(feature = "serde"))]
+fn print_json(_: &Grammer, _: usize) {
+    println!("ERROR: --format json needs grammer_sets to be built with the serde feature");
+    exit(1);
+}
+
+fn print_diagnostics(diagnostics: &[Diagnostic]) {
+    for diagnostic in diagnostics {
+        println!("{}", diagnostic);
+    }
+}
+
+// Prints every error found, and how many there were
+fn check_grammer(raw_grammer: &RawGrammer, infer_terminals: bool) -> Option<Grammer> {
+    match check(raw_grammer, infer_terminals) {
+        Ok(grammer) => Some(grammer),
+        Err(diagnostics) => {
+            print_diagnostics(&diagnostics);
+            println!("There were {} errors", diagnostics.len());
+            None
+        }
+    }
+}
+
+// Rewrite each file that isn't formatted, or with --check just say which ones aren't
+fn format_files(format_args: &args::FormatArgs) {
+    let mut unformatted = false;
+    for (path, buffer) in &format_args.files {
+        let text = match format_text(buffer) {
+            Ok(text) => text,
+            Err(diagnostics) => {
+                print_diagnostics(&diagnostics);
+                exit(2);
+            }
+        };
+        if text.as_bytes() == &buffer[..] {
+            continue;
+        }
+
+        if format_args.check {
+            println!("{} is not formatted", path);
+            unformatted = true;
+        } else if File::create(path).and_then(|mut file| file.write_all(text.as_bytes())).is_err() {
+            println!("ERROR: could not write {}", path);
+            exit(1);
+        }
+    }
+    if unformatted {
+        exit(1);
+    }
+}
+
 fn main () {
-    let args = args::parse_args();
+    match args::parse_args() {
+        args::Command::Format(format_args) => format_files(&format_args),
+        args::Command::Analyse(args) => analyse(*args),
+    }
+}
+
+fn analyse(args: args::Args) {
+    let raw_grammer = match parse(&args.input_buffer) {
+        Ok(g) => g,
+        Err(diagnostics) => {
+            print_diagnostics(&diagnostics);
+            exit(2);
+        }
+    };
+
+    if args.pretty {
+        print!("{}", format_raw_grammer(&raw_grammer));
+        return;
+    }
+
+    // The report shows the file as written, before its includes are pulled in
+    let written = args.report_path.as_ref().map(|_| format_raw_grammer(&raw_grammer));
+
+    let raw_grammer = match load_includes(raw_grammer, &args.input_path, &args.input_buffer) {
+        Ok(g) => g,
+        Err(diagnostics) => {
+            print_diagnostics(&diagnostics);
+            exit(2);
+        }
+    };
 
-    let mut raw_grammer = match parser::parse(&args.input_buffer) {
+    let grammer = match check_grammer(&raw_grammer, args.infer_terminals) {
         Some(g) => g,
-        None => exit(2),
+        None => exit(3),
     };
 
+    if let (Some(ref diff_path), Some(ref diff_buffer)) = (&args.diff_path, &args.diff_buffer) {
+        let other_raw_grammer = match parse(diff_buffer)
+            .and_then(|g| load_includes(g, diff_path, diff_buffer)) {
+            Ok(g) => g,
+            Err(diagnostics) => {
+                print_diagnostics(&diagnostics);
+                exit(2);
+            }
+        };
+
+        let other_grammer = match check_grammer(&other_raw_grammer, args.infer_terminals) {
+            Some(g) => g,
+            None => exit(3),
+        };
+
+        print_diff(&grammer, &other_grammer);
+        return;
+    }
+
+    if args.dot && args.slr {
+        let automaton = build_automaton(&grammer);
+        let table = build_slr_table(&grammer, &automaton);
+        print_dot_automaton(&grammer, &automaton, &table);
+        return;
+    }
+
+    if args.dot {
+        print_dot_graph(&grammer);
+        return;
+    }
+
+    if let (Some(ref report_path), Some(ref written)) = (&args.report_path, &written) {
+        let report = html_report(written, &grammer);
+        if File::create(report_path).and_then(|mut file| file.write_all(report.as_bytes())).is_err() {
+            println!("ERROR: could not write {}", report_path);
+            exit(1);
+        }
+        return;
+    }
+
+    if args.svg {
+        print!("{}", railroad_svg(&grammer));
+        return;
+    }
+
+    let tokens = if let Some(ref sentence) = args.sentence {
+        Some(tokens_for_sentence(&grammer, sentence))
+    } else if let Some(ref text_buffer) = args.text_buffer {
+        let lexer = build_lexer(&grammer);
+        let tokens = lexer.lex(&String::from_utf8_lossy(text_buffer));
+        Some(tokens.map(|tokens| tokens.iter().map(|token| token.id).collect()))
+    } else {
+        None
+    };
+
+    if let Some(tokens) = tokens {
+        let tokens = match tokens {
+            Ok(t) => t,
+            Err(diagnostics) => {
+                print_diagnostics(&diagnostics);
+                exit(4);
+            }
+        };
+
+        let tree = match parse_tokens(&grammer, &tokens) {
+            Some(t) => t,
+            None => {
+                println!("The sentence is not in the language of the grammer");
+                exit(4);
+            }
+        };
+
+        print_derivations(&grammer, &tree);
+        match args.tree_format.as_deref() {
+            Some("ascii") => print_ascii_tree(&grammer, &tree),
+            Some("dot") => print_dot_tree(&grammer, &tree),
+            _ => (),
+        }
+        return;
+    }
+
+    if let Some((ref symbol, ref entry)) = args.why_first {
+        if ! why_first(&grammer, symbol, entry) {
+            exit(5);
+        }
+        return;
+    }
+
+    if let Some((ref symbol, ref entry)) = args.why_follow {
+        if ! why_follow(&grammer, symbol, entry) {
+            exit(5);
+        }
+        return;
+    }
+
+    match args.format.as_str() {
+        "json" => {
+            print_json(&grammer, args.lookahead.unwrap_or(1));
+            return;
+        },
+        "latex" => {
+            print_tables(&grammer, TableFormat::Latex);
+            return;
+        },
+        "markdown" => {
+            print_tables(&grammer, TableFormat::Markdown);
+            return;
+        },
+        _ => (),
+    }
+
+    if args.slr {
+        let automaton = build_automaton(&grammer);
+        let table = build_slr_table(&grammer, &automaton);
+        print_table_report(&grammer, &automaton, &table);
+        return;
+    }
+
+    if let Some(k) = args.lookahead {
+        let first_k_sets = first_k_sets(&grammer, k);
+        let follow_k_sets = follow_k_sets(&grammer, &first_k_sets, k);
+        print_first_k_sets(&grammer, &first_k_sets, k);
+        print_follow_k_sets(&grammer, &follow_k_sets, k);
+
+        let conflicts = ll_k_conflicts(&grammer, &first_k_sets, &follow_k_sets, k);
+        print_conflicts(&grammer, &conflicts, k);
+        return;
+    }
+
+    let (first_sets, follow_sets) = if args.trace {
+        let first_sets = trace_first_sets(&grammer);
+        let follow_sets = trace_follow_sets(&grammer, &first_sets);
+        (first_sets, follow_sets)
+    } else {
+        let first_sets = first_sets(&grammer);
+        let follow_sets = follow_sets(&grammer, &first_sets);
+        (first_sets, follow_sets)
+    };
 
+    print_sets("First", &grammer, &first_sets);
+    print_sets("Follow", &grammer, &follow_sets);
 }
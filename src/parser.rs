@@ -1,10 +1,10 @@
-use nom::{alphanumeric, ErrorKind, IResult};
+use nom::{ErrorKind, IResult};
 use nom::verbose_errors::Err;
+use alphabet::literal_name;
+use grammer::Diagnostic;
 use raw_grammer::*;
 
-const START_ERR: u32 = 1;
-const TERMINALS_ERR: u32 = 2;
-const NONTERMINALS_ERR: u32 = 3;
+const SECTION_ERR: u32 = 4;
 const NAME_ERR: u32 = 5;
 const NAMES_ERR: u32 = 6;
 const RULES_ERR: u32 = 7;
@@ -12,19 +12,31 @@ const RULES_LIST_ERR: u32 = 8;
 const RULE_HEAD_ERR: u32 = 9;
 const RULE_ARROW_ERR: u32 = 10;
 const RULE_TERM_ERR: u32 = 11;
+const RULE_ALTERNATE_ERR: u32 = 12;
+const LITERAL_ERR: u32 = 13;
+const GROUP_ERR: u32 = 14;
+const INCLUDE_ERR: u32 = 15;
+const CHILD_NAME_ERR: u32 = 16;
+const ACTION_ERR: u32 = 17;
+const TOKEN_ERR: u32 = 18;
 
 fn error_code_to_str(code: u32) -> &'static str {
     match code {
-        START_ERR => "Expected ':Start:'",
-        TERMINALS_ERR => "Expected ':Terminals:'",
-        NONTERMINALS_ERR => "Expected ':NonTerminals:'",
-        NAME_ERR => "Expected a single alphanumeric name",
-        NAMES_ERR => "Expected a whitespace seperated list of alphanumeric names",
+        SECTION_ERR => "Expected a rule, or one of ':Start:', ':Terminals:', ':NonTerminals:', ':Rules:', ':Include:', ':Precedence:' or ':Tokens:'",
+        NAME_ERR => "Expected a single name",
+        NAMES_ERR => "Expected a whitespace separated list of names",
+        LITERAL_ERR => "Expected a closing quote after a literal, literals cannot be empty",
+        GROUP_ERR => "Expected '|' between the alternates of a group, or a bracket to close it",
+        INCLUDE_ERR => "Expected a path after ':Include:'",
+        CHILD_NAME_ERR => "Expected a symbol after the name of a child, names can't be used inside a group",
+        ACTION_ERR => "Expected an action block in braces after '=>', with every brace closed",
+        TOKEN_ERR => "Expected a /regex/ or a quoted literal, regexes cannot be empty",
         RULES_ERR => "Expected ':Rules:'",
-        RULES_LIST_ERR => "Expected a whitespace seperated list of rules",
-        RULE_HEAD_ERR => "Expected alphanumeric name for head of rule",
+        RULES_LIST_ERR => "Expected a whitespace separated list of rules",
+        RULE_HEAD_ERR => "Expected a name for head of rule",
         RULE_ARROW_ERR => "Expected '->'",
-        RULE_TERM_ERR => "Expected '.'",
+        RULE_TERM_ERR => "Expected '.' or '|'",
+        RULE_ALTERNATE_ERR => "Expected a whitespace separated list of names after '|'",
         _ => "Unknown Error code?"
     }
 }
@@ -36,113 +48,672 @@ fn error_kind_to_str(err: &ErrorKind) -> String {
     }
 }
 
-fn print_error(err: &Err<&[u8]>) {
+fn general(message: String) -> Diagnostic {
+    Diagnostic { location: None, message: message }
+}
+
+fn error_diagnostics(err: &Err<&[u8]>, diagnostics: &mut Vec<Diagnostic>) {
     match *err {
-        Err::Code(ref code) => println!("ERROR: There was an error {}", error_kind_to_str(code)),
+        Err::Code(ref code) => diagnostics.push(general(format!("There was an error {}", error_kind_to_str(code)))),
         Err::Node(ref code, ref errs) => {
-            println!(
-                "ERROR: There was a node error: {}\n It contained {} other errors", 
+            diagnostics.push(general(format!(
+                "There was a node error: {}\n It contained {} other errors",
                 error_kind_to_str(code),
                 errs.len()
-            );
-            let _: Vec<()> = errs.iter().map(|e| print_error(e)).collect();
+            )));
+            for e in errs {
+                error_diagnostics(e, diagnostics);
+            }
         },
-        Err::Position(ref code, ref pos) => {
-            println!("ERROR: There was an error at {}:\n{}", String::from_utf8_lossy(pos), error_kind_to_str(code));
+        Err::Position(ref code, pos) => {
+            diagnostics.push(general(format!(
+                "There was an error at {}:\n{}",
+                String::from_utf8_lossy(pos),
+                error_kind_to_str(code)
+            )));
         },
-        Err::NodePosition(ref code, ref pos, ref errs) => {
-            println!(
-                "ERROR: There was an node error at {}:\n{}\nThere were {} errors contained",
+        Err::NodePosition(ref code, pos, ref errs) => {
+            diagnostics.push(general(format!(
+                "There was an node error at {}:\n{}\nThere were {} errors contained",
                 String::from_utf8_lossy(pos),
                 error_kind_to_str(code),
                 errs.len()
-            );
+            )));
         }
     }
 }
 
-pub fn parse(input: &[u8]) -> Option<RawGrammer> {
+pub(crate) fn parse(input: &[u8]) -> Result<RawGrammer, Vec<Diagnostic>> {
     let result = parse_grammer(input);
 
     match result {
-        IResult::Done(leftover_input, grammer) => {
-            if leftover_input.len() > 0 {
-                println!(
+        IResult::Done(leftover_input, (sections, trailing)) => {
+            if ! leftover_input.is_empty() {
+                if let Some(diagnostic) = bad_name_char(input) {
+                    return Err(vec![diagnostic]);
+                }
+                Err(vec![general(format!(
                     "There was some leftover input?:\n{}\n==============\n",
                     String::from_utf8_lossy(leftover_input)
-                );
-                None
+                ))])
             } else {
-                Some(grammer)
+                Ok(merge_sections(sections, trailing).with_offsets(input.len()))
             }
         },
         IResult::Error(err) => {
-            print_error(&err);
-            None
+            // nom only knows which section failed, a stray char in a name is easier to point at
+            if let Some(diagnostic) = bad_name_char(input) {
+                return Err(vec![diagnostic]);
+            }
+            if let Some(diagnostic) = bad_name(input, &err) {
+                return Err(vec![diagnostic]);
+            }
+            let mut diagnostics = Vec::new();
+            error_diagnostics(&err, &mut diagnostics);
+            Err(diagnostics)
         },
         IResult::Incomplete(needed) => {
-            println!("ERROR: nom expected some more bytes, how many? {:?}", needed);
-            None
+            Err(vec![general(format!("nom expected some more bytes, how many? {:?}", needed))])
         }
     }
 }
 
-named!(parse_grammer <RawGrammer>,
-    ws!(do_parse!(
+// Sections can come in any order and any number of times, merge_sections puts them together
+enum Section {
+    Start(RawName),
+    Terminals(Vec<RawName>),
+    NonTerminals(Vec<RawName>),
+    Rules(Vec<RawRule>),
+    Include(RawInclude),
+    Precedence(Vec<RawPrecedence>),
+    Tokens(Vec<TokenEntry>),
+}
+
+enum TokenEntry {
+    Token(RawToken),
+    Skip(RawSkip),
+}
+
+// Each section comes with the comments in front of it, and for the declaration sections the
+// comments inside it and any on the line it ends. The comments after the last section are kept too
+type CommentedSection = (Vec<String>, Section);
+
+fn parse_grammer(input: &[u8]) -> IResult<&[u8], (Vec<CommentedSection>, Vec<String>)> {
+    let mut sections = Vec::new();
+    let mut rest = input;
+    loop {
+        let (after_comments, mut comments) = scan_comments(rest);
+        let (after_section, section) = match parse_section(after_comments) {
+            IResult::Done(after_section, section) => (after_section, section),
+            IResult::Error(Err::Position(ErrorKind::Custom(SECTION_ERR), _)) => break,
+            IResult::Error(err) => return IResult::Error(err),
+            IResult::Incomplete(needed) => return IResult::Incomplete(needed),
+        };
+        rest = after_section;
+
+        // The comments of a rule are kept on the rule. A declaration section takes the space
+        // after it too, the comments in that are left for whatever comes next
+        if ! matches!(section, Section::Rules(_)) {
+            let text = &after_comments[..after_comments.len() - after_section.len()];
+            let (inside, content_length) = comments_in(text);
+            comments.extend(inside);
+            let (after_line, line_comment) = line_end_comment(&after_comments[content_length..]);
+            comments.extend(line_comment);
+            rest = after_line;
+        }
+        sections.push((comments, section));
+    }
+
+    let (rest, trailing) = scan_comments(rest);
+    match add_return_error!(rest, ErrorKind::Custom(SECTION_ERR), eof!()) {
+        IResult::Done(rest, _) => IResult::Done(rest, (sections, trailing)),
+        IResult::Error(err) => IResult::Error(err),
+        IResult::Incomplete(needed) => IResult::Incomplete(needed),
+    }
+}
+
+// The tag alone decides which section comes next, so an error inside the section is reported
+// as it is instead of as there being no section here
+fn parse_section(input: &[u8]) -> IResult<&[u8], Section> {
+    let tag = scan_space(input).0;
+    if tag.starts_with(b":Start:") {
+        parse_start_section(input).map(Section::Start)
+    } else if tag.starts_with(b":Terminals:") {
+        parse_terminals_section(input).map(Section::Terminals)
+    } else if tag.starts_with(b":NonTerminals:") {
+        parse_non_terminals_section(input).map(Section::NonTerminals)
+    } else if tag.starts_with(b":Rules:") {
+        parse_rules_section(input).map(Section::Rules)
+    } else if tag.starts_with(b":Include:") {
+        parse_include_section(input).map(Section::Include)
+    } else if tag.starts_with(b":Precedence:") {
+        parse_precedence_section(input).map(Section::Precedence)
+    } else if tag.starts_with(b":Tokens:") {
+        parse_tokens_section(input).map(Section::Tokens)
+    } else {
+        IResult::Error(error_position!(ErrorKind::Custom(SECTION_ERR), input))
+    }
+}
+
+// The tags are complete! so that running out of input ends the sections instead of asking for more
+named!(parse_start_section <RawName>,
+    sep!(parse_space, preceded!(
+        complete!(tag!(":Start:")),
         add_return_error!(
-            ErrorKind::Custom(START_ERR), 
-            tag!(":Start:") 
-        ) >>
-        start: add_return_error!(
             ErrorKind::Custom(NAME_ERR),
-            call!(parse_symbol_name) 
-        ) >>
-        add_return_error!(
-            ErrorKind::Custom(TERMINALS_ERR),
-            tag!(":Terminals:")
-        ) >>
-        terminals: add_return_error!(
-            ErrorKind::Custom(NAMES_ERR),
-            call!(parse_name_vec)
-        ) >> 
-        add_return_error!(
-            ErrorKind::Custom(NONTERMINALS_ERR),
-            tag!(":NonTerminals:")
+            call!(parse_raw_name)
+        )
+    ))
+);
+
+named!(parse_terminals_section <Vec<RawName>>,
+    sep!(parse_space, preceded!(
+        complete!(tag!(":Terminals:")),
+        call!(parse_name_vec)
+    ))
+);
+
+named!(parse_non_terminals_section <Vec<RawName>>,
+    sep!(parse_space, preceded!(
+        complete!(tag!(":NonTerminals:")),
+        call!(parse_name_vec)
+    ))
+);
+
+// The path is everything up to the next whitespace, or between double quotes if it has spaces in it.
+// Like rules, the span is measured from the end of the input until parse fixes it up
+fn parse_include_section(input: &[u8]) -> IResult<&[u8], RawInclude> {
+    let after_tag = match complete!(scan_space(input).0, tag!(":Include:")) {
+        IResult::Done(rest, _) => scan_space(rest).0,
+        IResult::Error(err) => return IResult::Error(err),
+        IResult::Incomplete(needed) => return IResult::Incomplete(needed),
+    };
+
+    let (rest, path) = match parse_include_path(after_tag) {
+        IResult::Done(rest, path) => (rest, path),
+        IResult::Error(err) => return IResult::Error(err),
+        IResult::Incomplete(needed) => return IResult::Incomplete(needed),
+    };
+
+    let span = Span { start: after_tag.len(), end: rest.len(), file: 0 };
+    let mut include = RawInclude {
+        path: String::from(String::from_utf8_lossy(path)),
+        prefix: None,
+        span: span
+    };
+
+    let after_space = scan_space(rest).0;
+    if let IResult::Done(after_as, _) = complete!(after_space, tag!("as")) {
+        if let IResult::Done(after_prefix, prefix) = parse_symbol_name(scan_space(after_as).0) {
+            include.prefix = Some(prefix);
+            return IResult::Done(after_prefix, include);
+        }
+    }
+    IResult::Done(rest, include)
+}
+
+fn parse_include_path(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    let (rest, path) = match input.first() {
+        Some(&b'"') => match input[1..].iter().position(|c| *c == b'"') {
+            Some(length) => (&input[length + 2..], &input[1..length + 1]),
+            None => return IResult::Error(error_position!(ErrorKind::Custom(INCLUDE_ERR), input)),
+        },
+        _ => {
+            let length = input.iter()
+                .position(|c| c.is_ascii_whitespace())
+                .unwrap_or(input.len());
+            (&input[length..], &input[..length])
+        }
+    };
+    if path.is_empty() {
+        return IResult::Error(error_position!(ErrorKind::Custom(INCLUDE_ERR), input));
+    }
+    IResult::Done(rest, path)
+}
+
+named!(parse_precedence_section <Vec<RawPrecedence>>,
+    sep!(parse_space, preceded!(
+        complete!(tag!(":Precedence:")),
+        many0!(call!(parse_precedence_level))
+    ))
+);
+
+named!(parse_precedence_level <RawPrecedence>,
+    sep!(parse_space, do_parse!(
+        associativity: alt!(
+            complete!(tag!("%left")) => { |_| Associativity::Left } |
+            complete!(tag!("%right")) => { |_| Associativity::Right } |
+            complete!(tag!("%nonassoc")) => { |_| Associativity::NonAssoc }
         ) >>
-        non_terminals: add_return_error!(
+        names: add_return_error!(
             ErrorKind::Custom(NAMES_ERR),
-            call!(parse_name_vec)
+            sep!(parse_space, many1!(call!(parse_precedence_name)))
         ) >>
+        (RawPrecedence {
+            associativity: associativity,
+            names: names
+        })
+    ))
+);
+
+// Like rules, the spans of names are measured from the end of the input until parse fixes them up
+fn parse_raw_name(input: &[u8]) -> IResult<&[u8], RawName> {
+    with_span(input, parse_symbol_name(input))
+}
+
+fn parse_precedence_name(input: &[u8]) -> IResult<&[u8], RawName> {
+    with_span(input, alt!(input, parse_symbol_name | parse_literal))
+}
+
+fn with_span<'a>(input: &[u8], result: IResult<&'a [u8], String>) -> IResult<&'a [u8], RawName> {
+    match result {
+        IResult::Done(rest, name) => {
+            let span = Span { start: input.len(), end: rest.len(), file: 0 };
+            IResult::Done(rest, RawName { name: name, span: span })
+        },
+        IResult::Error(err) => IResult::Error(err),
+        IResult::Incomplete(needed) => IResult::Incomplete(needed),
+    }
+}
+
+named!(parse_tokens_section <Vec<TokenEntry>>,
+    sep!(parse_space, preceded!(
+        complete!(tag!(":Tokens:")),
+        many0!(call!(parse_token_entry))
+    ))
+);
+
+// Either Name = pattern, or %skip pattern for text that sits between tokens
+fn parse_token_entry(input: &[u8]) -> IResult<&[u8], TokenEntry> {
+    let start = scan_space(input).0;
+    if let IResult::Done(after_skip, _) = complete!(start, tag!("%skip")) {
+        return match parse_token_pattern(scan_space(after_skip).0) {
+            IResult::Done(rest, pattern) => {
+                let span = Span { start: start.len(), end: rest.len(), file: 0 };
+                IResult::Done(rest, TokenEntry::Skip(RawSkip { pattern: pattern, span: span }))
+            },
+            _ => IResult::Error(error_position!(ErrorKind::Custom(TOKEN_ERR), after_skip)),
+        };
+    }
+
+    let (after_name, name) = match parse_symbol_name(start) {
+        IResult::Done(rest, name) => (rest, name),
+        IResult::Error(err) => return IResult::Error(err),
+        IResult::Incomplete(needed) => return IResult::Incomplete(needed),
+    };
+    let after_equals = match complete!(scan_space(after_name).0, tag!("=")) {
+        IResult::Done(rest, _) => scan_space(rest).0,
+        IResult::Error(err) => return IResult::Error(err),
+        IResult::Incomplete(needed) => return IResult::Incomplete(needed),
+    };
+    match parse_token_pattern(after_equals) {
+        IResult::Done(rest, pattern) => {
+            let span = Span { start: start.len(), end: rest.len(), file: 0 };
+            IResult::Done(rest, TokenEntry::Token(RawToken { name: name, pattern: pattern, span: span }))
+        },
+        _ => IResult::Error(error_position!(ErrorKind::Custom(TOKEN_ERR), after_equals)),
+    }
+}
+
+// A regex goes between slashes, and \/ is a slash inside one. Any other escape is left for the regex
+fn parse_token_pattern(input: &[u8]) -> IResult<&[u8], TokenPattern> {
+    if input.first() != Some(&b'/') {
+        return match parse_literal(input) {
+            IResult::Done(rest, name) => IResult::Done(rest, TokenPattern::Literal(name)),
+            IResult::Error(err) => IResult::Error(err),
+            IResult::Incomplete(needed) => IResult::Incomplete(needed),
+        };
+    }
+
+    let mut text = Vec::new();
+    let mut position = 1;
+    while position < input.len() {
+        match input[position] {
+            b'\\' if input.get(position + 1) == Some(&b'/') => {
+                text.push(b'/');
+                position += 2;
+            },
+            b'\\' if position + 1 < input.len() => {
+                text.extend_from_slice(&input[position..position + 2]);
+                position += 2;
+            },
+            b'/' => {
+                if text.is_empty() {
+                    break;
+                }
+                let regex = String::from(String::from_utf8_lossy(&text));
+                return IResult::Done(&input[position + 1..], TokenPattern::Regex(regex));
+            },
+            c => {
+                text.push(c);
+                position += 1;
+            }
+        }
+    }
+    IResult::Error(error_position!(ErrorKind::Custom(TOKEN_ERR), input))
+}
+
+// Declarations are all optional, so a name list can be empty
+named!(parse_name_vec <Vec<RawName>>, 
+    sep!(parse_space, many0!(call!(parse_raw_name)))
+);
+
+fn comment_place(section: &Section) -> CommentPlace {
+    match *section {
+        Section::Start(_) => CommentPlace::Start,
+        Section::Terminals(_) => CommentPlace::Terminals,
+        Section::NonTerminals(_) => CommentPlace::NonTerminals,
+        Section::Rules(_) => CommentPlace::Rules,
+        Section::Include(_) => CommentPlace::Include,
+        Section::Precedence(_) => CommentPlace::Precedence,
+        Section::Tokens(_) => CommentPlace::Tokens,
+    }
+}
+
+// The comments are put in the order they get printed in, so printing and parsing again gives
+// back the same grammer
+fn merge_sections(sections: Vec<CommentedSection>, trailing: Vec<String>) -> RawGrammer {
+    let mut grammer = RawGrammer {
+        starts: Vec::new(),
+        terminals: Vec::new(),
+        non_terminals: Vec::new(),
+        rules: Vec::new(),
+        precedence: Vec::new(),
+        tokens: Vec::new(),
+        skips: Vec::new(),
+        comments: Vec::new(),
+        includes: Vec::new(),
+        files: Vec::new()
+    };
+
+    for (comments, section) in sections {
+        let place = comment_place(&section);
+        grammer.comments.extend(comments.into_iter().map(|comment| (place, comment)));
+        match section {
+            Section::Start(name) => grammer.starts.push(name),
+            Section::Terminals(names) => grammer.terminals.extend(names),
+            Section::NonTerminals(names) => grammer.non_terminals.extend(names),
+            Section::Rules(rules) => grammer.rules.extend(rules),
+            Section::Include(include) => grammer.includes.push(include),
+            Section::Precedence(levels) => grammer.precedence.extend(levels),
+            Section::Tokens(entries) => {
+                for entry in entries {
+                    match entry {
+                        TokenEntry::Token(token) => grammer.tokens.push(token),
+                        TokenEntry::Skip(skip) => grammer.skips.push(skip),
+                    }
+                }
+            },
+        }
+    }
+    grammer.comments.extend(trailing.into_iter().map(|comment| (CommentPlace::End, comment)));
+    grammer.comments.sort_by_key(|&(place, _)| place);
+    grammer
+}
+
+// The rules section is parsed without a separator between the tag and each rule, so
+// that the comments in front of a rule are still there for parse_comments to keep
+named!(parse_rules_section <Vec<RawRule>>,
+    do_parse!(
         add_return_error!(
             ErrorKind::Custom(RULES_ERR),
-            tag!(":Rules:")
-        ) >>
-        rules: add_return_error!(
-            ErrorKind::Custom(RULES_LIST_ERR),
-            call!(parse_rules)
+            complete!(tag!(":Rules:"))
         ) >>
-        eof!() >>
-        (RawGrammer {
-            start: start,
-            terminals: terminals,
-            non_terminals: non_terminals,
-            rules: rules
-        })
-    ))
+        rules: call!(parse_rules) >>
+        (rules)
+    )
 );
 
-named!(parse_name_vec <Vec<String>>, 
-    ws!(many1!(call!(parse_symbol_name)))
+// The rules end where something that isn't a head and an arrow comes up. Once a rule has
+// started, whatever goes wrong in it is the error
+fn parse_rules(input: &[u8]) -> IResult<&[u8], Vec<RawRule>> {
+    let mut rules = Vec::new();
+    let mut rest = input;
+    while rule_starts(rest) {
+        match parse_rule(rest) {
+            IResult::Done(after_rule, rule) => {
+                rules.extend(rule);
+                rest = after_rule;
+            },
+            IResult::Error(err) => return IResult::Error(err),
+            IResult::Incomplete(needed) => return IResult::Incomplete(needed),
+        }
+    }
+
+    if rules.is_empty() {
+        return IResult::Error(error_position!(ErrorKind::Custom(RULES_LIST_ERR), input));
+    }
+    IResult::Done(rest, rules)
+}
+
+fn rule_starts(input: &[u8]) -> bool {
+    match parse_symbol_name(scan_space(input).0) {
+        IResult::Done(after_head, _) => scan_space(after_head).0.starts_with(b"->"),
+        _ => false,
+    }
+}
+
+// Spans are taken from the length of the remaining input, since that is all a parser can see.
+// parse converts them to offsets from the start once the whole input has been consumed
+type NamedSymbol = (Option<String>, RawSymbol);
+
+fn parse_alternate(input: &[u8]) -> IResult<&[u8], (Vec<NamedSymbol>, Span)> {
+    let mut alternate = Vec::new();
+    let mut span = Span::default();
+    let mut rest = input;
+    loop {
+        let (after_space, _) = scan_space(rest);
+        match parse_named_item(after_space) {
+            IResult::Done(after_item, symbol) => {
+                if alternate.is_empty() {
+                    span.start = after_space.len();
+                }
+                alternate.push(symbol);
+                span.end = after_item.len();
+                rest = after_item;
+            },
+            _ => break,
+        }
+    }
+
+    if alternate.is_empty() {
+        return IResult::Error(error_position!(ErrorKind::Many1, input));
+    }
+    IResult::Done(rest, (alternate, span))
+}
+
+// Any item can be given a name for the child it becomes in a parse tree, as in left:Expr
+fn parse_named_item(input: &[u8]) -> IResult<&[u8], NamedSymbol> {
+    if let IResult::Done(after_name, name) = parse_symbol_name(input) {
+        if after_name.first() == Some(&b':') {
+            return match parse_item(&after_name[1..]) {
+                IResult::Done(rest, symbol) => IResult::Done(rest, (Some(name), symbol)),
+                _ => IResult::Error(error_position!(ErrorKind::Custom(CHILD_NAME_ERR), after_name)),
+            };
+        }
+    }
+
+    match parse_item(input) {
+        IResult::Done(rest, symbol) => IResult::Done(rest, (None, symbol)),
+        IResult::Error(err) => IResult::Error(err),
+        IResult::Incomplete(needed) => IResult::Incomplete(needed),
+    }
+}
+
+// An item is a name, a literal or a bracketed group, and may be followed directly by '?', '*' or '+'
+fn parse_item(input: &[u8]) -> IResult<&[u8], RawSymbol> {
+    let (rest, symbol) = match parse_primary(input) {
+        IResult::Done(rest, symbol) => (rest, symbol),
+        IResult::Error(err) => return IResult::Error(err),
+        IResult::Incomplete(needed) => return IResult::Incomplete(needed),
+    };
+
+    match rest.first() {
+        Some(&b'?') => IResult::Done(&rest[1..], RawSymbol::Optional(symbol.into_alternates())),
+        Some(&b'*') => IResult::Done(&rest[1..], RawSymbol::Repeat(symbol.into_alternates())),
+        Some(&b'+') => IResult::Done(&rest[1..], RawSymbol::RepeatOne(symbol.into_alternates())),
+        _ => IResult::Done(rest, symbol),
+    }
+}
+
+fn parse_primary(input: &[u8]) -> IResult<&[u8], RawSymbol> {
+    let close = match input.first() {
+        Some(&b'(') => b')',
+        Some(&b'[') => b']',
+        Some(&b'{') => b'}',
+        _ => return map!(input, alt!(parse_symbol_name | parse_literal), RawSymbol::Name),
+    };
+
+    let mut alternates = Vec::new();
+    let mut rest = &input[1..];
+    loop {
+        match parse_alternate(rest) {
+            IResult::Done(after, (alternate, _)) => {
+                // Only the top level of a rule alternate makes children with names
+                if alternate.iter().any(|(name, _)| name.is_some()) {
+                    return IResult::Error(error_position!(ErrorKind::Custom(CHILD_NAME_ERR), rest));
+                }
+                alternates.push(alternate.into_iter().map(|(_, symbol)| symbol).collect());
+                rest = scan_space(after).0;
+            },
+            _ => return IResult::Error(error_position!(ErrorKind::Custom(GROUP_ERR), rest)),
+        }
+
+        match rest.first() {
+            Some(&b'|') => rest = &rest[1..],
+            Some(c) if *c == close => break,
+            _ => return IResult::Error(error_position!(ErrorKind::Custom(GROUP_ERR), rest)),
+        }
+    }
+
+    let symbol = match close {
+        b')' => RawSymbol::Group(alternates),
+        b']' => RawSymbol::Optional(alternates),
+        _ => RawSymbol::Repeat(alternates),
+    };
+    IResult::Done(&rest[1..], symbol)
+}
+
+// An alternate of a rule, as opposed to one inside a group, can be followed by a @Label,
+// a %prec Name and an action block => { ... }, in any order. The head is filled in by the caller
+fn parse_rule_alternate(input: &[u8]) -> IResult<&[u8], RawRule> {
+    let (mut rest, (alternate, span)) = match parse_alternate(input) {
+        IResult::Done(rest, result) => (rest, result),
+        IResult::Error(err) => return IResult::Error(err),
+        IResult::Incomplete(needed) => return IResult::Incomplete(needed),
+    };
+
+    let (child_names, alternate) = alternate.into_iter().unzip();
+    let mut rule = RawRule {
+        head: String::new(),
+        alternate: alternate,
+        child_names: child_names,
+        label: None,
+        precedence: None,
+        action: None,
+        span: span,
+        comments: Vec::new()
+    };
+
+    loop {
+        let after_space = scan_space(rest).0;
+        if let (None, IResult::Done(after, label)) = (&rule.label, parse_label(after_space)) {
+            rule.label = Some(label);
+            rest = after;
+        } else if let (None, IResult::Done(after, name)) = (&rule.precedence, parse_prec(after_space)) {
+            rule.precedence = Some(name);
+            rest = after;
+        } else if let (None, IResult::Done(after, action)) = (&rule.action, parse_action(after_space)) {
+            rule.action = Some(action);
+            rest = after;
+        } else {
+            break;
+        }
+    }
+    IResult::Done(rest, rule)
+}
+
+named!(parse_label <String>,
+    preceded!(complete!(tag!("@")), parse_symbol_name)
 );
 
-named!(parse_rules <Vec<RawRule>>,
-    many1!(
-        call!(parse_rule)
+named!(parse_prec <String>,
+    preceded!(
+        complete!(tag!("%prec")),
+        preceded!(parse_space, alt!(parse_symbol_name | parse_literal))
     )
 );
 
-named!(parse_rule <RawRule>,
-    ws!(do_parse!(
+fn parse_action(input: &[u8]) -> IResult<&[u8], String> {
+    match complete!(input, tag!("=>")) {
+        IResult::Done(rest, _) => parse_action_block(scan_space(rest).0),
+        IResult::Error(err) => IResult::Error(err),
+        IResult::Incomplete(needed) => IResult::Incomplete(needed),
+    }
+}
+
+// The text of an action is kept as it is written, only braces are counted to find its end.
+// Braces inside quotes don't count, so an action can hold strings like "}"
+fn parse_action_block(after_arrow: &[u8]) -> IResult<&[u8], String> {
+    if after_arrow.first() != Some(&b'{') {
+        return IResult::Error(error_position!(ErrorKind::Custom(ACTION_ERR), after_arrow));
+    }
+
+    let mut depth = 0;
+    let mut quote = None;
+    let mut position = 0;
+    while position < after_arrow.len() {
+        let c = after_arrow[position];
+        match quote {
+            Some(_) if c == b'\\' => position += 1,
+            Some(q) if c == q => quote = None,
+            Some(_) => (),
+            None if c == b'"' || c == b'\'' => quote = Some(c),
+            None if c == b'{' => depth += 1,
+            None if c == b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    let text = String::from_utf8_lossy(&after_arrow[1..position]);
+                    return IResult::Done(&after_arrow[position + 1..], String::from(text.trim()));
+                }
+            },
+            None => (),
+        }
+        position += 1;
+    }
+    IResult::Error(error_position!(ErrorKind::Custom(ACTION_ERR), after_arrow))
+}
+
+// The comments directly in front of a rule, then any inside it and any on the line it ends on,
+// are attached to its first alternate
+fn parse_rule(input: &[u8]) -> IResult<&[u8], Vec<RawRule>> {
+    let (after_comments, mut comments) = scan_comments(input);
+    match parse_rule_statement(after_comments) {
+        IResult::Done(rest, mut rules) => {
+            comments.extend(comments_in(&after_comments[..after_comments.len() - rest.len()]).0);
+            let (rest, line_comment) = line_end_comment(rest);
+            comments.extend(line_comment);
+            rules[0].comments = comments;
+            IResult::Done(rest, rules)
+        },
+        IResult::Error(err) => IResult::Error(err),
+        IResult::Incomplete(needed) => IResult::Incomplete(needed),
+    }
+}
+
+named!(parse_rule_statement <Vec<RawRule>>,
+    do_parse!(
+        rules: call!(parse_rule_body) >>
+        add_return_error!(
+            ErrorKind::Custom(RULE_TERM_ERR),
+            complete!(tag!("."))
+        ) >>
+        (rules)
+    )
+);
+
+named!(parse_rule_body <Vec<RawRule>>,
+    sep!(parse_space, do_parse!(
         head: add_return_error!(
             ErrorKind::Custom(RULE_HEAD_ERR),
             call!(parse_symbol_name) 
@@ -151,25 +722,277 @@ named!(parse_rule <RawRule>,
             ErrorKind::Custom(RULE_ARROW_ERR),
             tag!("->") 
         ) >>
-        alternate: add_return_error!(
+        first: add_return_error!(
             ErrorKind::Custom(NAMES_ERR),
-            call!(parse_name_vec)
-        ) >>
-        add_return_error!(
-            ErrorKind::Custom(RULE_TERM_ERR),
-            tag!(".")
+            call!(parse_rule_alternate)
         ) >>
-        (RawRule {
-            head: head,
-            alternate: alternate
-        })
+        rest: many0!(sep!(parse_space, preceded!(
+            tag!("|"),
+            add_return_error!(
+                ErrorKind::Custom(RULE_ALTERNATE_ERR),
+                call!(parse_rule_alternate)
+            )
+        ))) >>
+        (Some(first).into_iter().chain(rest)
+            .map(|rule| RawRule { head: head.clone(), ..rule })
+            .collect()
+        )
     ))
 );
 
-named!(parse_symbol_name <String>,
-    do_parse!(
-        name: call!(alphanumeric) >>
-        (String::from(String::from_utf8_lossy(name))
-        )
-    )
-);
+// A literal is any text between matching single or double quotes, with a backslash escaping
+// the next character. The result is the display name of the terminal the literal stands for
+fn parse_literal(input: &[u8]) -> IResult<&[u8], String> {
+    let quote = match input.first() {
+        Some(&b'"') => b'"',
+        Some(&b'\'') => b'\'',
+        _ => return IResult::Error(error_position!(ErrorKind::Custom(LITERAL_ERR), input)),
+    };
+
+    let mut text = Vec::new();
+    let mut position = 1;
+    while position < input.len() {
+        match input[position] {
+            b'\\' if position + 1 < input.len() => {
+                text.push(input[position + 1]);
+                position += 2;
+            },
+            c if c == quote => {
+                if text.is_empty() {
+                    break;
+                }
+                let name = literal_name(&String::from_utf8_lossy(&text));
+                return IResult::Done(&input[position + 1..], name);
+            },
+            c => {
+                text.push(c);
+                position += 1;
+            }
+        }
+    }
+    IResult::Error(error_position!(ErrorKind::Custom(LITERAL_ERR), input))
+}
+
+// Skip any whitespace and comments at the front of the input, returning what is left and
+// the text of each comment. Line comments start with '#' or '//', block comments are
+// between '/*' and '*/'. An unterminated block comment is left for the next parser to reject
+fn scan_space(input: &[u8]) -> (&[u8], Vec<&[u8]>) {
+    let mut comments = Vec::new();
+    let mut position = 0;
+    loop {
+        let rest = &input[position..];
+        let length = if rest.first().is_some_and(|c| c.is_ascii_whitespace()) {
+            1
+        } else if rest.starts_with(b"#") || rest.starts_with(b"//") {
+            let length = rest.iter().position(|c| *c == b'\n').unwrap_or(rest.len());
+            comments.push(&rest[..length]);
+            length
+        } else if rest.starts_with(b"/*") {
+            match rest.windows(2).skip(2).position(|w| w == b"*/") {
+                Some(index) => {
+                    comments.push(&rest[..index + 4]);
+                    index + 4
+                },
+                None => break,
+            }
+        } else {
+            break;
+        };
+        position += length;
+    }
+    (&input[position..], comments)
+}
+
+fn parse_space(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    let (rest, _) = scan_space(input);
+    IResult::Done(rest, &input[..input.len() - rest.len()])
+}
+
+fn scan_comments(input: &[u8]) -> (&[u8], Vec<String>) {
+    let (rest, comments) = scan_space(input);
+    let comments = comments.iter()
+        .map(|c| String::from(String::from_utf8_lossy(c).trim_end()))
+        .collect();
+    (rest, comments)
+}
+
+// A comment that starts on the same line as the end of a section or rule, after nothing but spaces
+fn line_end_comment(input: &[u8]) -> (&[u8], Option<String>) {
+    let indent = input.iter().take_while(|c| **c == b' ' || **c == b'\t').count();
+    let rest = &input[indent..];
+    if ! (rest.starts_with(b"#") || rest.starts_with(b"/")) {
+        return (input, None);
+    }
+    match scan_space(rest).1.first() {
+        Some(comment) if comment.as_ptr() == rest.as_ptr() => (
+            &rest[comment.len()..],
+            Some(String::from(String::from_utf8_lossy(comment).trim_end()))
+        ),
+        _ => (input, None),
+    }
+}
+
+// Every comment in text the parser has already accepted, up to the end of the last thing in it
+// that isn't a comment, and the length of the text up to there. Names, literals, regexes,
+// include paths and action blocks are stepped over whole, since a '#' or '/' in them isn't a comment
+fn comments_in(input: &[u8]) -> (Vec<String>, usize) {
+    let mut comments = Vec::new();
+    let mut rest = input;
+    loop {
+        let (after_space, found) = scan_comments(rest);
+        if after_space.is_empty() {
+            return (comments, input.len() - rest.len());
+        }
+        comments.extend(found);
+        rest = after_space;
+
+        if let IResult::Done(after, _) = parse_symbol_name(rest) {
+            rest = after;
+        } else if let IResult::Done(after, _) = parse_literal(rest) {
+            rest = after;
+        } else if let IResult::Done(after, _) = parse_token_pattern(rest) {
+            rest = after;
+        } else if let IResult::Done(after_tag, _) = complete!(rest, tag!(":Include:")) {
+            let (after_space, found) = scan_comments(after_tag);
+            comments.extend(found);
+            rest = match parse_include_path(after_space) {
+                IResult::Done(after, _) => after,
+                _ => after_space,
+            };
+        } else if let IResult::Done(after_arrow, _) = complete!(rest, tag!("=>")) {
+            let (after_space, found) = scan_comments(after_arrow);
+            comments.extend(found);
+            rest = match parse_action_block(after_space) {
+                IResult::Done(after, _) => after,
+                _ => after_space,
+            };
+        } else {
+            rest = &rest[next_char(rest).map_or(1, |(_, length)| length)..];
+        }
+    }
+}
+
+pub(crate) static NAME_RULES: &str =
+    "Names start with a letter, digit or '_', and after that may also contain '-' and '''";
+
+// Everything outside of names, literals and comments that the grammer syntax is made of
+static SYNTAX_CHARS: &str = ":->.|()[]{}?*+%@";
+
+// Decode the char at the front of the input, and how many bytes it takes up
+fn next_char(input: &[u8]) -> Option<(char, usize)> {
+    let length = match *input.first()? {
+        byte if byte < 0x80 => 1,
+        byte if byte >= 0xf0 => 4,
+        byte if byte >= 0xe0 => 3,
+        _ => 2,
+    };
+    let bytes = input.get(..length)?;
+    ::std::str::from_utf8(bytes).ok()
+        .and_then(|s| s.chars().next())
+        .map(|c| (c, length))
+}
+
+fn name_length(input: &[u8]) -> usize {
+    let mut position = 0;
+    while let Some((c, length)) = next_char(&input[position..]) {
+        let allowed = if position == 0 {
+            c.is_alphanumeric() || c == '_'
+        } else {
+            // A dash right before '>' is the arrow of a rule, not part of the head
+            c.is_alphanumeric() || c == '_' || c == '\''
+                || (c == '-' && input.get(position + 1) != Some(&b'>'))
+        };
+        if ! allowed {
+            break;
+        }
+        position += length;
+    }
+    position
+}
+
+// A name is a letter, digit or underscore, followed by any number of letters, digits,
+// underscores, dashes and primes. Letters and digits are whatever Unicode counts as one,
+// so expr_list, match-item, E' and Ausdrücke are all names
+fn parse_symbol_name(input: &[u8]) -> IResult<&[u8], String> {
+    match name_length(input) {
+        0 => IResult::Error(error_position!(ErrorKind::Custom(NAME_ERR), input)),
+        length => IResult::Done(
+            &input[length..],
+            String::from(String::from_utf8_lossy(&input[..length]))
+        ),
+    }
+}
+
+// Whether a name could be written as it is in a grammer file, for grammers built in code
+pub(crate) fn is_name(name: &str) -> bool {
+    ! name.is_empty() && name_length(name.as_bytes()) == name.len()
+}
+
+// Walk the input the way the parser splits it up, and find the first char that
+// can't be part of a name, a literal, a comment or the syntax around them
+fn find_bad_name_char(input: &[u8]) -> Option<(char, usize)> {
+    let mut rest = input;
+    loop {
+        rest = scan_space(rest).0;
+        let position = input.len() - rest.len();
+        let (c, length) = match next_char(rest) {
+            Some(next) => next,
+            None => return rest.first().map(|byte| (char::from(*byte), position)),
+        };
+
+        if let IResult::Done(after, _) = parse_include_section(rest) {
+            rest = after;
+        } else if let IResult::Done(after, _) = parse_action(rest) {
+            rest = after;
+        } else if let IResult::Done(after, _) = parse_token_entry(rest) {
+            rest = after;
+        } else if let IResult::Done(after, _) = parse_symbol_name(rest) {
+            rest = after;
+        } else if let IResult::Done(after, _) = parse_literal(rest) {
+            rest = after;
+        } else if SYNTAX_CHARS.contains(c) {
+            rest = &rest[length..];
+        } else {
+            return Some((c, position));
+        }
+    }
+}
+
+// Where a list of names was expected but didn't start, if that is what went wrong
+fn names_error_position<'a>(err: &Err<&'a [u8]>) -> Option<&'a [u8]> {
+    match *err {
+        Err::Position(ErrorKind::Custom(NAMES_ERR), position)
+        | Err::NodePosition(ErrorKind::Custom(NAMES_ERR), position, _) => Some(position),
+        Err::Node(_, ref errs) | Err::NodePosition(_, _, ref errs) => errs.iter().filter_map(names_error_position).next(),
+        _ => None,
+    }
+}
+
+// Name the token that stands where a name should be, and where it is
+fn bad_name(input: &[u8], err: &Err<&[u8]>) -> Option<Diagnostic> {
+    let rest = names_error_position(err)?;
+    let position = input.len() - rest.len();
+    let (line, col) = Span { start: position, end: position, file: 0 }.line_col(input);
+    let token: String = String::from_utf8_lossy(rest).chars()
+        .enumerate()
+        .take_while(|(index, c)| *index == 0 || ! c.is_whitespace())
+        .map(|(_, c)| c)
+        .take(20)
+        .collect();
+    let message = if token.is_empty() {
+        format!("the input ends at line {}, column {}. {}", line, col, error_code_to_str(NAMES_ERR))
+    } else {
+        format!("'{}' at line {}, column {} is not a name. {}", token, line, col, error_code_to_str(NAMES_ERR))
+    };
+    Some(general(message))
+}
+
+fn bad_name_char(input: &[u8]) -> Option<Diagnostic> {
+    find_bad_name_char(input).map(|(c, position)| {
+        let (line, col) = Span { start: position, end: position, file: 0 }.line_col(input);
+        general(format!(
+            "'{}' at line {}, column {} cannot be used in a name. {}",
+            c, line, col, NAME_RULES
+        ))
+    })
+}
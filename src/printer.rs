@@ -0,0 +1,239 @@
+use grammer::Diagnostic;
+use parser::parse;
+use raw_grammer::{Associativity, CommentPlace, RawGrammer, RawName, RawRule, RawSymbol, TokenPattern};
+
+// Declaration lists are wrapped to stay inside this many columns
+const LINE_WIDTH: usize = 80;
+
+fn text_width(text: &str) -> usize {
+    text.chars().count()
+}
+
+fn pad(text: &str, width: usize) -> String {
+    let mut result = String::from(text);
+    result.extend((text_width(text)..width).map(|_| ' '));
+    result
+}
+
+// The names follow the first word, and every line after the first starts under the first name
+fn format_names(first: &str, names: &[RawName]) -> String {
+    let indent = if first.is_empty() { String::new() } else { pad("", text_width(first) + 1) };
+    let mut result = String::from(first);
+    let mut line_width = text_width(first);
+    for name in names.iter().map(|name| &name.name) {
+        if line_width > text_width(&indent) && line_width + 1 + text_width(name) > LINE_WIDTH {
+            result.push('\n');
+            result.push_str(&indent);
+            line_width = text_width(&indent);
+        } else if line_width > 0 {
+            result.push(' ');
+            line_width += 1;
+        }
+        result.push_str(name);
+        line_width += text_width(name);
+    }
+    result
+}
+
+fn format_comments(raw_grammer: &RawGrammer, place: CommentPlace) -> String {
+    raw_grammer.comments.iter()
+        .filter(|&&(comment_place, _)| comment_place == place)
+        .map(|(_, comment)| format!("{}\n", comment))
+        .collect()
+}
+
+fn format_alternates(alternates: &[Vec<RawSymbol>]) -> String {
+    alternates.iter()
+        .map(|alternate| format_alternate(alternate))
+        .collect::<Vec<String>>()
+        .join(" | ")
+}
+
+// Optionals and repeats always come out bracketed, x? and x* are only a shorthand for those
+fn format_symbol(symbol: &RawSymbol) -> String {
+    match *symbol {
+        RawSymbol::Name(ref name) => name.clone(),
+        RawSymbol::Group(ref alternates) => format!("( {} )", format_alternates(alternates)),
+        RawSymbol::Optional(ref alternates) => format!("[ {} ]", format_alternates(alternates)),
+        RawSymbol::Repeat(ref alternates) => format!("{{ {} }}", format_alternates(alternates)),
+        RawSymbol::RepeatOne(ref alternates) => {
+            if alternates.len() == 1 && alternates[0].len() == 1 {
+                format!("{}+", format_symbol(&alternates[0][0]))
+            } else {
+                format!("( {} )+", format_alternates(alternates))
+            }
+        }
+    }
+}
+
+fn format_token_pattern(pattern: &TokenPattern) -> String {
+    match *pattern {
+        TokenPattern::Regex(ref text) => format!("/{}/", text.replace('/', "\\/")),
+        TokenPattern::Literal(ref name) => name.clone(),
+    }
+}
+
+fn format_rule_alternate(rule: &RawRule) -> String {
+    let mut result = rule.alternate.iter()
+        .zip(&rule.child_names)
+        .map(|(symbol, child_name)| match *child_name {
+            Some(ref child_name) => format!("{}:{}", child_name, format_symbol(symbol)),
+            None => format_symbol(symbol),
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+    if let Some(ref label) = rule.label {
+        result.push_str(&format!(" @{}", label));
+    }
+    if let Some(ref name) = rule.precedence {
+        result.push_str(&format!(" %prec {}", name));
+    }
+    if let Some(ref action) = rule.action {
+        result.push_str(&format!(" => {{ {} }}", action));
+    }
+    result
+}
+
+fn format_alternate(alternate: &[RawSymbol]) -> String {
+    alternate.iter()
+        .map(format_symbol)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// Consecutive rules with the same head are written as one rule, with '|' between the alternates.
+// Heads are padded out to the width so that the arrows of every rule line up
+fn format_rule_group(rules: &[&RawRule], width: usize) -> String {
+    let mut result = String::new();
+    for comment in &rules[0].comments {
+        result.push_str(comment);
+        result.push('\n');
+    }
+    result.push_str(&format!("{} -> {}", pad(&rules[0].head, width), format_rule_alternate(rules[0])));
+
+    let indent = pad("", width);
+    for rule in &rules[1..] {
+        result.push('\n');
+        result.push_str(&format!("{}  | {}", indent, format_rule_alternate(rule)));
+    }
+    result.push('.');
+    result
+}
+
+pub(crate) fn group_rules(rules: &[RawRule]) -> Vec<Vec<&RawRule>> {
+    let mut groups: Vec<Vec<&RawRule>> = Vec::new();
+    for rule in rules {
+        // A commented rule starts a new group so its comments stay in front of it
+        let same_head = rule.comments.is_empty()
+            && groups.last().is_some_and(|group| group[0].head == rule.head);
+        if same_head {
+            groups.last_mut().unwrap().push(rule);
+        } else {
+            groups.push(vec![rule]);
+        }
+    }
+    groups
+}
+
+/// The grammer file text of a grammer, in the canonical layout.
+///
+/// Comments go in front of the section they were written with. A declaration section that was
+/// left out stays left out, unless it only had comments in it.
+pub fn format_raw_grammer(raw_grammer: &RawGrammer) -> String {
+    let mut result = format_comments(raw_grammer, CommentPlace::Include);
+    for include in &raw_grammer.includes {
+        if include.path.contains(char::is_whitespace) {
+            result.push_str(&format!(":Include: \"{}\"", include.path));
+        } else {
+            result.push_str(&format!(":Include: {}", include.path));
+        }
+        if let Some(ref prefix) = include.prefix {
+            result.push_str(&format!(" as {}", prefix));
+        }
+        result.push('\n');
+    }
+    if ! result.is_empty() {
+        result.push('\n');
+    }
+
+    result.push_str(&format_comments(raw_grammer, CommentPlace::Start));
+    for start in &raw_grammer.starts {
+        result.push_str(&format!(":Start:\n{}\n\n", start.name));
+    }
+
+    let declarations = [
+        (CommentPlace::Terminals, ":Terminals:", &raw_grammer.terminals),
+        (CommentPlace::NonTerminals, ":NonTerminals:", &raw_grammer.non_terminals),
+    ];
+    for &(place, tag, names) in &declarations {
+        let comments = format_comments(raw_grammer, place);
+        if ! names.is_empty() || ! comments.is_empty() {
+            result.push_str(&comments);
+            result.push_str(&format!("{}\n{}\n\n", tag, format_names("", names)));
+        }
+    }
+
+    let comments = format_comments(raw_grammer, CommentPlace::Precedence);
+    if ! raw_grammer.precedence.is_empty() || ! comments.is_empty() {
+        result.push_str(&comments);
+        result.push_str(":Precedence:\n");
+        for level in &raw_grammer.precedence {
+            let associativity = match level.associativity {
+                Associativity::Left => "%left",
+                Associativity::Right => "%right",
+                Associativity::NonAssoc => "%nonassoc",
+            };
+            result.push_str(&format!("{}\n", format_names(associativity, &level.names)));
+        }
+        result.push('\n');
+    }
+
+    let comments = format_comments(raw_grammer, CommentPlace::Tokens);
+    if ! raw_grammer.tokens.is_empty() || ! raw_grammer.skips.is_empty() || ! comments.is_empty() {
+        result.push_str(&comments);
+        result.push_str(":Tokens:\n");
+        let width = raw_grammer.tokens.iter().map(|token| text_width(&token.name)).max().unwrap_or(0);
+        for token in &raw_grammer.tokens {
+            result.push_str(&format!("{} = {}\n", pad(&token.name, width), format_token_pattern(&token.pattern)));
+        }
+        for skip in &raw_grammer.skips {
+            result.push_str(&format!("%skip {}\n", format_token_pattern(&skip.pattern)));
+        }
+        result.push('\n');
+    }
+
+    // The rules section needs at least one rule in it
+    result.push_str(&format_comments(raw_grammer, CommentPlace::Rules));
+    if ! raw_grammer.rules.is_empty() {
+        result.push_str(":Rules:\n");
+    }
+    let width = raw_grammer.rules.iter().map(|rule| text_width(&rule.head)).max().unwrap_or(0);
+    for (index, group) in group_rules(&raw_grammer.rules).iter().enumerate() {
+        // A blank line sets a commented rule apart from the one before it
+        if index > 0 && ! group[0].comments.is_empty() {
+            result.push('\n');
+        }
+        result.push_str(&format_rule_group(group, width));
+        result.push('\n');
+    }
+
+    let comments = format_comments(raw_grammer, CommentPlace::End);
+    if ! comments.is_empty() {
+        result.push('\n');
+        result.push_str(&comments);
+    }
+    result
+}
+
+/// Format the text of a grammer file, and make sure the result parses back to the same grammer.
+/// Only the spans may differ, since moving the text around is the point.
+pub fn format_text(input: &[u8]) -> Result<String, Vec<Diagnostic>> {
+    let raw_grammer = parse(input)?;
+    let text = format_raw_grammer(&raw_grammer);
+    let formatted = parse(text.as_bytes())?;
+    if formatted.without_spans() != raw_grammer.without_spans() {
+        let message = String::from("The formatted grammer does not parse back to the same grammer");
+        return Err(vec![Diagnostic { location: None, message: message }]);
+    }
+    Ok(text)
+}
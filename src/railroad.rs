@@ -0,0 +1,305 @@
+use std::collections::HashSet;
+use alphabet::{SymbolId, SymbolType};
+use grammer::Grammer;
+
+// Every part of a diagram sits on a horizontal track, and reaches up above it and down below it
+#[derive(PartialEq)]
+enum Diagram {
+    Terminal(String),
+    NonTerminal(String),
+    Sequence(Vec<Diagram>),
+    Choice(Vec<Diagram>),
+    // Zero or more times round
+    Repeat(Box<Diagram>),
+    // At least once round
+    OneOrMore(Box<Diagram>),
+}
+
+const RADIUS: usize = 10;
+const GAP: usize = 10;
+const CHAR_WIDTH: usize = 8;
+const BOX_HEIGHT: usize = 24;
+const TITLE_HEIGHT: usize = 30;
+const MARGIN: usize = 20;
+
+struct Size {
+    width: usize,
+    up: usize,
+    down: usize,
+}
+
+// Names and literals go into SVG and HTML as text and attributes
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Where the next row of a choice or repeat goes, far enough below the last for the curves
+fn next_row(y: usize, above: &Size, below: &Size) -> usize {
+    y + (above.down + GAP + below.up).max(2 * RADIUS)
+}
+
+impl Diagram {
+    fn size(&self) -> Size {
+        match *self {
+            Diagram::Terminal(ref text) | Diagram::NonTerminal(ref text) => Size {
+                width: text.chars().count() * CHAR_WIDTH + 20,
+                up: BOX_HEIGHT / 2,
+                down: BOX_HEIGHT / 2,
+            },
+            Diagram::Sequence(ref items) => {
+                let sizes: Vec<Size> = items.iter().map(Diagram::size).collect();
+                Size {
+                    width: sizes.iter().map(|size| size.width).sum::<usize>() + GAP * sizes.len().saturating_sub(1),
+                    up: sizes.iter().map(|size| size.up).max().unwrap_or(0),
+                    down: sizes.iter().map(|size| size.down).max().unwrap_or(0),
+                }
+            },
+            Diagram::Choice(ref rows) => {
+                let sizes: Vec<Size> = rows.iter().map(Diagram::size).collect();
+                let mut y = 0;
+                for pair in sizes.windows(2) {
+                    y = next_row(y, &pair[0], &pair[1]);
+                }
+                Size {
+                    width: sizes.iter().map(|size| size.width).max().unwrap_or(0) + 4 * RADIUS,
+                    up: sizes[0].up,
+                    down: y + sizes[sizes.len() - 1].down,
+                }
+            },
+            Diagram::Repeat(ref item) => {
+                let size = item.size();
+                let item_y = next_row(0, &Size { width: 0, up: 0, down: 0 }, &size);
+                let loop_y = next_row(item_y, &size, &Size { width: 0, up: 0, down: 0 });
+                Size { width: size.width + 4 * RADIUS, up: 0, down: loop_y }
+            },
+            Diagram::OneOrMore(ref item) => {
+                let size = item.size();
+                let loop_y = next_row(0, &size, &Size { width: 0, up: 0, down: 0 });
+                Size { width: size.width + 4 * RADIUS, up: size.up, down: loop_y }
+            },
+        }
+    }
+
+    // Draw with the track starting at x, y
+    fn render(&self, x: usize, y: usize, out: &mut String) {
+        let size = self.size();
+        match *self {
+            Diagram::Terminal(ref text) | Diagram::NonTerminal(ref text) => {
+                let top = y - BOX_HEIGHT / 2;
+                let center = x + size.width / 2;
+                if let Diagram::NonTerminal(_) = *self {
+                    out.push_str(&format!("<a href=\"#{}\">", escape(text)));
+                    out.push_str(&format!(
+                        "<rect class=\"nonterminal\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+                        x, top, size.width, BOX_HEIGHT
+                    ));
+                    out.push_str(&format!("<text x=\"{}\" y=\"{}\">{}</text></a>\n", center, y, escape(text)));
+                } else {
+                    out.push_str(&format!(
+                        "<rect class=\"terminal\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\"/>",
+                        x, top, size.width, BOX_HEIGHT, BOX_HEIGHT / 2
+                    ));
+                    out.push_str(&format!("<text x=\"{}\" y=\"{}\">{}</text>\n", center, y, escape(text)));
+                }
+            },
+            Diagram::Sequence(ref items) => {
+                let mut position = x;
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        out.push_str(&format!("<path d=\"M{} {}h{}\"/>\n", position, y, GAP));
+                        position += GAP;
+                    }
+                    item.render(position, y, out);
+                    position += item.size().width;
+                }
+            },
+            Diagram::Choice(ref rows) => {
+                let right = x + size.width;
+                let mut row_y = y;
+                for (index, row) in rows.iter().enumerate() {
+                    let row_size = row.size();
+                    if index > 0 {
+                        row_y = next_row(row_y, &rows[index - 1].size(), &row_size);
+                        out.push_str(&format!(
+                            "<path d=\"M{} {}q{} 0 {} {}V{}q0 {} {} {}\"/>\n",
+                            x, y, RADIUS, RADIUS, RADIUS, row_y - RADIUS, RADIUS, RADIUS, RADIUS
+                        ));
+                        out.push_str(&format!(
+                            "<path d=\"M{} {}q{} 0 {} -{}V{}q0 -{} {} -{}\"/>\n",
+                            right - 2 * RADIUS, row_y, RADIUS, RADIUS, RADIUS, y + RADIUS, RADIUS, RADIUS, RADIUS
+                        ));
+                    } else {
+                        out.push_str(&format!("<path d=\"M{} {}h{}\"/>\n", x, y, 2 * RADIUS));
+                        out.push_str(&format!("<path d=\"M{} {}H{}\"/>\n", right - 2 * RADIUS, y, right));
+                    }
+                    row.render(x + 2 * RADIUS, row_y, out);
+                    out.push_str(&format!("<path d=\"M{} {}H{}\"/>\n", x + 2 * RADIUS + row_size.width, row_y, right - 2 * RADIUS));
+                }
+            },
+            Diagram::Repeat(ref item) => {
+                let item_size = item.size();
+                let none = Size { width: 0, up: 0, down: 0 };
+                let item_y = next_row(y, &none, &item_size);
+                let loop_y = next_row(item_y, &item_size, &none);
+                let right = x + size.width;
+
+                // Straight past, down into the item, back up after it, and round again below it
+                out.push_str(&format!("<path d=\"M{} {}H{}\"/>\n", x, y, right));
+                out.push_str(&format!(
+                    "<path d=\"M{} {}q{} 0 {} {}V{}q0 {} {} {}\"/>\n",
+                    x, y, RADIUS, RADIUS, RADIUS, item_y - RADIUS, RADIUS, RADIUS, RADIUS
+                ));
+                item.render(x + 2 * RADIUS, item_y, out);
+                out.push_str(&format!(
+                    "<path d=\"M{} {}H{}q{} 0 {} -{}V{}q0 -{} {} -{}\"/>\n",
+                    x + 2 * RADIUS + item_size.width, item_y, right - 2 * RADIUS,
+                    RADIUS, RADIUS, RADIUS, y + RADIUS, RADIUS, RADIUS, RADIUS
+                ));
+                loop_back(x, right, item_y, loop_y, out);
+            },
+            Diagram::OneOrMore(ref item) => {
+                let item_size = item.size();
+                let loop_y = next_row(y, &item_size, &Size { width: 0, up: 0, down: 0 });
+                let right = x + size.width;
+
+                out.push_str(&format!("<path d=\"M{} {}h{}\"/>\n", x, y, 2 * RADIUS));
+                item.render(x + 2 * RADIUS, y, out);
+                out.push_str(&format!("<path d=\"M{} {}H{}\"/>\n", x + 2 * RADIUS + item_size.width, y, right));
+                loop_back(x, right, y, loop_y, out);
+            },
+        }
+    }
+}
+
+// From the end of an item at y, down to loop_y, back along and up into its start again
+fn loop_back(x: usize, right: usize, y: usize, loop_y: usize, out: &mut String) {
+    out.push_str(&format!(
+        "<path d=\"M{} {}q{} 0 {} {}V{}q0 {} -{} {}H{}q-{} 0 -{} -{}V{}q0 -{} {} -{}\"/>\n",
+        right - 2 * RADIUS, y, RADIUS, RADIUS, RADIUS, loop_y - RADIUS,
+        RADIUS, RADIUS, RADIUS, x + 2 * RADIUS,
+        RADIUS, RADIUS, RADIUS, y + RADIUS, RADIUS, RADIUS, RADIUS
+    ));
+}
+
+// N -> a b N | Empty, with no other rules for N, is a list of a b. These are drawn as a loop
+// wherever N is used, which is also how { } comes out once it has been desugared
+fn list_tail(grammer: &Grammer, id: SymbolId) -> Option<&[SymbolId]> {
+    let rules: Vec<&[SymbolId]> = grammer.rules.iter()
+        .filter(|rule| rule.head == id)
+        .map(|rule| rule.alternate.as_slice())
+        .collect();
+    if rules.len() != 2 {
+        return None;
+    }
+
+    let (body, empty) = if rules[0].is_empty() { (rules[1], rules[0]) } else { (rules[0], rules[1]) };
+    let (last, rest) = body.split_last()?;
+    if ! empty.is_empty() || *last != id || rest.is_empty() || rest.contains(&id) {
+        return None;
+    }
+    Some(rest)
+}
+
+fn sequence(grammer: &Grammer, alternate: &[SymbolId], inlining: &mut HashSet<SymbolId>) -> Diagram {
+    let items: Vec<Diagram> = alternate.iter()
+        .map(|id| {
+            let name = String::from(grammer.name(*id));
+            if grammer.alphabet.is_type(id, SymbolType::Terminal) {
+                return Diagram::Terminal(name);
+            }
+            match list_tail(grammer, *id) {
+                Some(body) if inlining.insert(*id) => {
+                    let body = sequence(grammer, body, inlining);
+                    inlining.remove(id);
+                    Diagram::Repeat(Box::new(body))
+                },
+                _ => Diagram::NonTerminal(name),
+            }
+        })
+        .collect();
+
+    // a b { a b } is drawn as a b going round at least once
+    let mut folded: Vec<Diagram> = Vec::new();
+    for item in items {
+        let body_length = match item {
+            Diagram::Repeat(ref body) => match **body {
+                Diagram::Sequence(ref body) if body.len() <= folded.len() && folded.ends_with(body) => body.len(),
+                _ => 0,
+            },
+            _ => 0,
+        };
+        if body_length == 0 {
+            folded.push(item);
+            continue;
+        }
+        let start = folded.len() - body_length;
+        let mut body: Vec<Diagram> = folded.split_off(start);
+        let body = if body.len() == 1 { body.pop().unwrap() } else { Diagram::Sequence(body) };
+        folded.push(Diagram::OneOrMore(Box::new(body)));
+    }
+    Diagram::Sequence(folded)
+}
+
+fn head_diagram(grammer: &Grammer, head: SymbolId) -> Diagram {
+    let mut rows: Vec<Diagram> = grammer.rules.iter()
+        .filter(|rule| rule.head == head)
+        .map(|rule| sequence(grammer, &rule.alternate, &mut HashSet::new()))
+        .collect();
+    if rows.len() == 1 {
+        rows.pop().unwrap()
+    } else {
+        Diagram::Choice(rows)
+    }
+}
+
+/// An SVG page of railroad diagrams for the rules of the grammer.
+///
+/// One diagram for each nonterminal with rules, one under the other, titled with its name.
+/// Lists that are drawn as loops where they are used don't get a diagram of their own.
+pub fn railroad_svg(grammer: &Grammer) -> String {
+    let heads: Vec<SymbolId> = grammer.alphabet.get_type_set(SymbolType::NonTerminal).iter()
+        .cloned()
+        .filter(|id| grammer.rules.iter().any(|rule| rule.head == *id))
+        .filter(|id| *id == grammer.start || list_tail(grammer, *id).is_none())
+        .collect();
+
+    let mut body = String::new();
+    let mut top = 0;
+    let mut width = 0;
+    for head in heads {
+        let diagram = head_diagram(grammer, head);
+        let size = diagram.size();
+        let name = escape(grammer.name(head));
+        let track = top + TITLE_HEIGHT + size.up + GAP;
+        let end = MARGIN + GAP + size.width + GAP;
+
+        body.push_str(&format!("<g id=\"{}\">\n", name));
+        body.push_str(&format!("<text class=\"title\" x=\"{}\" y=\"{}\">{}</text>\n", MARGIN, top + TITLE_HEIGHT / 2, name));
+        body.push_str(&format!("<path d=\"M{} {}v{}m0 -{}h{}\"/>\n", MARGIN, track - GAP, 2 * GAP, GAP, GAP));
+        diagram.render(MARGIN + GAP, track, &mut body);
+        body.push_str(&format!("<path d=\"M{} {}h{}m0 -{}v{}\"/>\n", end - GAP, track, GAP, GAP, 2 * GAP));
+        body.push_str("</g>\n");
+
+        top = track + size.down + GAP + MARGIN;
+        width = width.max(end + MARGIN);
+    }
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+        width, top, width, top
+    );
+    svg.push_str("<style>\n");
+    svg.push_str("path { fill: none; stroke: black; stroke-width: 1.5; }\n");
+    svg.push_str("rect { stroke: black; stroke-width: 1.5; }\n");
+    svg.push_str("rect.terminal { fill: #e8f0fe; }\n");
+    svg.push_str("rect.nonterminal { fill: #fef7e0; }\n");
+    svg.push_str("text { font-family: monospace; font-size: 13px; text-anchor: middle; dominant-baseline: central; }\n");
+    svg.push_str("text.title { font-weight: bold; text-anchor: start; }\n");
+    svg.push_str("</style>\n");
+    svg.push_str(&body);
+    svg.push_str("</svg>\n");
+    svg
+}
@@ -0,0 +1,220 @@
+// Byte offsets into the source of a grammer, start inclusive and end exclusive. The file is an
+// index into the files of the RawGrammer, 0 being the file that was parsed or loaded first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub(crate) struct Span {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) file: usize
+}
+
+impl Span {
+    // Lines and columns both count from 1
+    pub(crate) fn line_col(&self, input: &[u8]) -> (usize, usize) {
+        let before = &input[..self.start];
+        let line = before.iter().filter(|c| **c == b'\n').count() + 1;
+        let col = before.iter().rev().take_while(|c| **c != b'\n').count() + 1;
+        (line, col)
+    }
+}
+
+// The EBNF forms hold the alternates written inside them. x? is read as [ x ], x* as { x },
+// and a postfix operator on a group applies to the alternates of the group
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum RawSymbol {
+    Name(String),
+    // ( a | b )
+    Group(Vec<Vec<RawSymbol>>),
+    // [ a | b ] or x?
+    Optional(Vec<Vec<RawSymbol>>),
+    // { a | b } or x*
+    Repeat(Vec<Vec<RawSymbol>>),
+    // x+
+    RepeatOne(Vec<Vec<RawSymbol>>),
+}
+
+impl RawSymbol {
+    // Only names are left once a grammer has been desugared
+    pub(crate) fn name(&self) -> Option<&String> {
+        match *self {
+            RawSymbol::Name(ref name) => Some(name),
+            _ => None,
+        }
+    }
+
+    // Every name in the symbol, in the order they were written
+    pub(crate) fn names(&self) -> Vec<&String> {
+        match *self {
+            RawSymbol::Name(ref name) => vec![name],
+            RawSymbol::Group(ref alternates)
+            | RawSymbol::Optional(ref alternates)
+            | RawSymbol::Repeat(ref alternates)
+            | RawSymbol::RepeatOne(ref alternates) => alternates.iter()
+                .flat_map(|alternate| alternate.iter().flat_map(RawSymbol::names))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn into_alternates(self) -> Vec<Vec<RawSymbol>> {
+        match self {
+            RawSymbol::Group(alternates) => alternates,
+            symbol => vec![vec![symbol]],
+        }
+    }
+}
+
+// Every alternate of a rule is its own RawRule, in source order. The span covers just the alternate,
+// and the comments are those written in front of the rule or inside it, markers included
+// The precedence is the name given with %prec, if there was one. There is a child name for each
+// symbol of the alternate, and the label and action are kept as they were written
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RawRule {
+    pub(crate) head: String,
+    pub(crate) alternate: Vec<RawSymbol>,
+    pub(crate) child_names: Vec<Option<String>>,
+    pub(crate) label: Option<String>,
+    pub(crate) precedence: Option<String>,
+    pub(crate) action: Option<String>,
+    pub(crate) span: Span,
+    pub(crate) comments: Vec<String>
+}
+
+/// How operators on the same precedence level group.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Associativity {
+    /// `a op b op c` is `(a op b) op c`, from `%left`.
+    Left,
+    /// `a op b op c` is `a op (b op c)`, from `%right`.
+    Right,
+    /// `a op b op c` is an error, from `%nonassoc`.
+    NonAssoc,
+}
+
+// A name in :Start:, a declaration section or a precedence level, and where it was written
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RawName {
+    pub(crate) name: String,
+    pub(crate) span: Span
+}
+
+// One %left, %right or %nonassoc line of the :Precedence: section. Later lines bind tighter
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RawPrecedence {
+    pub(crate) associativity: Associativity,
+    pub(crate) names: Vec<RawName>
+}
+
+// :Include: path, or :Include: path as Prefix
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RawInclude {
+    pub(crate) path: String,
+    pub(crate) prefix: Option<String>,
+    pub(crate) span: Span
+}
+
+/// What a :Tokens: entry matches. Literals are kept as their quoted name, like in a rule.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TokenPattern {
+    /// A regex, without its slashes.
+    Regex(String),
+    /// A quoted literal, as its name in double quotes.
+    Literal(String),
+}
+
+// Name = /regex/ or Name = "text" in the :Tokens: section
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RawToken {
+    pub(crate) name: String,
+    pub(crate) pattern: TokenPattern,
+    pub(crate) span: Span
+}
+
+// %skip pattern in the :Tokens: section
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RawSkip {
+    pub(crate) pattern: TokenPattern,
+    pub(crate) span: Span
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SourceFile {
+    pub(crate) path: String,
+    pub(crate) text: Vec<u8>
+}
+
+// Comments outside of rules are kept with the section they were written in or in front of, or
+// at the end of the file after the last section. The places sort in the order they are printed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum CommentPlace {
+    Include,
+    Start,
+    Terminals,
+    NonTerminals,
+    Precedence,
+    Tokens,
+    Rules,
+    End,
+}
+
+/// A grammer as it was written, before its names are checked and given ids.
+///
+/// Without a :Start: section the start symbol is the head of the first rule. Every :Start: is
+/// kept, so that check can point at one that disagrees with the first. The files are only
+/// filled in once the grammer is loaded from disk, see `load`.
+#[derive(Debug, PartialEq)]
+pub struct RawGrammer {
+    pub(crate) starts: Vec<RawName>,
+    pub(crate) terminals: Vec<RawName>,
+    pub(crate) non_terminals: Vec<RawName>,
+    pub(crate) rules: Vec<RawRule>,
+    pub(crate) precedence: Vec<RawPrecedence>,
+    pub(crate) tokens: Vec<RawToken>,
+    pub(crate) skips: Vec<RawSkip>,
+    pub(crate) comments: Vec<(CommentPlace, String)>,
+    pub(crate) includes: Vec<RawInclude>,
+    pub(crate) files: Vec<SourceFile>
+}
+
+impl RawGrammer {
+    pub(crate) fn spans_mut(&mut self) -> Vec<&mut Span> {
+        let mut spans: Vec<&mut Span> = Vec::new();
+        spans.extend(self.starts.iter_mut().map(|start| &mut start.span));
+        spans.extend(self.terminals.iter_mut().map(|name| &mut name.span));
+        spans.extend(self.non_terminals.iter_mut().map(|name| &mut name.span));
+        spans.extend(self.rules.iter_mut().map(|rule| &mut rule.span));
+        spans.extend(self.precedence.iter_mut().flat_map(|level| level.names.iter_mut()).map(|name| &mut name.span));
+        spans.extend(self.tokens.iter_mut().map(|token| &mut token.span));
+        spans.extend(self.skips.iter_mut().map(|skip| &mut skip.span));
+        spans.extend(self.includes.iter_mut().map(|include| &mut include.span));
+        spans
+    }
+
+    // Turn spans measured from the end of the input into offsets from the start
+    pub(crate) fn with_offsets(mut self, input_len: usize) -> RawGrammer {
+        for span in self.spans_mut() {
+            span.start = input_len - span.start;
+            span.end = input_len - span.end;
+        }
+        self
+    }
+
+    // Spans say where things are in the text, so they are cleared to compare grammers written differently
+    pub(crate) fn without_spans(mut self) -> RawGrammer {
+        for span in self.spans_mut() {
+            *span = Span::default();
+        }
+        self
+    }
+
+    // Where a span is, as path:line:col, for the front of an error message
+    pub(crate) fn location(&self, span: Span) -> String {
+        match self.files.get(span.file) {
+            Some(file) => {
+                let (line, col) = span.line_col(&file.text);
+                format!("{}:{}:{}", file.path, line, col)
+            },
+            None => format!("byte {}", span.start),
+        }
+    }
+}
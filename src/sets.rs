@@ -0,0 +1,301 @@
+use std::collections::{BTreeMap, BTreeSet};
+use alphabet::{EMPTY_NAME, SymbolId, SymbolType};
+use grammer::Grammer;
+
+/// An entry of a First or Follow set. `End` is the end of the input, and is only in Follow sets.
+/// Entries order terminals by declaration, with `Empty` and then `End` after all of them.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SetEntry {
+    /// A terminal.
+    Id(SymbolId),
+    /// The empty string.
+    Empty,
+    /// The end of the input.
+    End
+}
+
+/// A First or Follow set for every symbol. Both the symbols and their sets iterate in declaration order.
+pub type SetMap = BTreeMap<SymbolId, BTreeSet<SetEntry>>;
+
+// Why an entry is in a set. Positions index into the alternate of the rule
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub(crate) enum Reason {
+    // A terminal is in its own first set
+    Terminal,
+    // The entry is in the first set of the symbol at position, and everything before it is nullable
+    FirstOf { rule: usize, position: usize },
+    // Every symbol in the alternate is nullable
+    Nullable { rule: usize },
+    // The end of input follows the start symbol
+    Start,
+    // The entry is in the first set of the symbol at source, which comes after position
+    // with only nullable symbols between them
+    FollowFirst { rule: usize, position: usize, source: usize },
+    // Everything after position is nullable, so the follow set of the head is included
+    FollowHead { rule: usize, position: usize },
+}
+
+pub(crate) fn entry_name(grammer: &Grammer, entry: &SetEntry) -> String {
+    match *entry {
+        SetEntry::Id(id) => String::from(grammer.name(id)),
+        SetEntry::Empty => String::from(EMPTY_NAME),
+        SetEntry::End => String::from("End"),
+    }
+}
+
+pub(crate) fn describe_reason(grammer: &Grammer, reason: &Reason) -> String {
+    match *reason {
+        Reason::Terminal => String::from("a terminal is in its own first set"),
+        Reason::FirstOf { rule, position } => {
+            let alternate = &grammer.rules[rule].alternate;
+            format!(
+                "from First({}) at position {} of `{}`",
+                grammer.name(alternate[position]),
+                position,
+                grammer.rule_to_string(&grammer.rules[rule])
+            )
+        },
+        Reason::Nullable { rule } => format!(
+            "every symbol of `{}` is nullable",
+            grammer.rule_to_string(&grammer.rules[rule])
+        ),
+        Reason::Start => String::from("the end of input follows the start symbol"),
+        Reason::FollowFirst { rule, position, source } => {
+            let alternate = &grammer.rules[rule].alternate;
+            format!(
+                "from First({}) at position {}, after {} at position {} of `{}`",
+                grammer.name(alternate[source]),
+                source,
+                grammer.name(alternate[position]),
+                position,
+                grammer.rule_to_string(&grammer.rules[rule])
+            )
+        },
+        Reason::FollowHead { rule, position } => {
+            let head = grammer.rules[rule].head;
+            format!(
+                "from Follow({}), everything after position {} of `{}` is nullable",
+                grammer.name(head),
+                position,
+                grammer.rule_to_string(&grammer.rules[rule])
+            )
+        },
+    }
+}
+
+// Accumulates one kind of set, optionally printing every addition as it happens
+struct SetBuilder<'a> {
+    grammer: &'a Grammer,
+    label: &'static str,
+    set_map: SetMap,
+    trace: bool,
+}
+
+impl<'a> SetBuilder<'a> {
+    fn new(grammer: &'a Grammer, label: &'static str, trace: bool) -> SetBuilder<'a> {
+        SetBuilder {
+            grammer: grammer,
+            label: label,
+            set_map: BTreeMap::new(),
+            trace: trace,
+        }
+    }
+
+    fn add(&mut self, target: SymbolId, entry: SetEntry, reason: Reason) -> bool {
+        let inserted_something_new = self.set_map.get_mut(&target).unwrap().insert(entry);
+        if ! inserted_something_new {
+            return false;
+        }
+
+        if self.trace {
+            println!(
+                "    {}({}) += {}, {}",
+                self.label,
+                self.grammer.name(target),
+                entry_name(self.grammer, &entry),
+                describe_reason(self.grammer, &reason)
+            );
+        }
+        true
+    }
+
+    // Add every entry of a set except Empty, since that never passes through a sequence
+    fn add_all(&mut self, target: SymbolId, source: &BTreeSet<SetEntry>, reason: Reason) -> bool {
+        let mut inserted_something_new = false;
+        for entry in source.iter().filter(|e| **e != SetEntry::Empty) {
+            inserted_something_new = self.add(target, *entry, reason) || inserted_something_new;
+        }
+        inserted_something_new
+    }
+
+    fn trace_pass(&self, pass: usize) {
+        if self.trace {
+            println!("{} pass {}", self.label, pass);
+        }
+    }
+
+    fn trace_rule(&self, rule: usize) {
+        if self.trace {
+            println!("  Rule {}: {}", rule, self.grammer.rule_to_string(&self.grammer.rules[rule]));
+        }
+    }
+}
+
+// The first set of a sequence of symbols. Empty is included only if every symbol is nullable
+pub(crate) fn sequence_first_set(sequence: &[SymbolId], first_sets: &SetMap) -> BTreeSet<SetEntry> {
+    let mut result = BTreeSet::new();
+    for id in sequence {
+        let id_first_set = first_sets.get(id).unwrap();
+        result.extend(id_first_set.iter().filter(|e| **e != SetEntry::Empty));
+
+        if ! id_first_set.contains(&SetEntry::Empty) {
+            return result;
+        }
+    }
+    result.insert(SetEntry::Empty);
+    result
+}
+
+fn build_first_sets(grammer: &Grammer, trace: bool) -> SetMap {
+    let mut builder = SetBuilder::new(grammer, "First", trace);
+
+    // All first sets start as empty
+    for id in grammer.alphabet.get_type_set(SymbolType::NonTerminal) {
+        builder.set_map.insert(*id, BTreeSet::new());
+    }
+
+    // For all terminals T, T is in First(T)
+    builder.trace_pass(0);
+    for id in grammer.alphabet.get_type_set(SymbolType::Terminal) {
+        builder.set_map.insert(*id, BTreeSet::new());
+        builder.add(*id, SetEntry::Id(*id), Reason::Terminal);
+    }
+
+    let mut pass = 0;
+    let mut need_another_pass = true;
+    while need_another_pass {
+        need_another_pass = false;
+        pass += 1;
+        builder.trace_pass(pass);
+
+        for (index, rule) in grammer.rules.iter().enumerate() {
+            builder.trace_rule(index);
+
+            let mut all_nullable = true;
+            for (position, id) in rule.alternate.iter().enumerate() {
+                let id_first_set = builder.set_map.get(id).unwrap().clone();
+                let reason = Reason::FirstOf { rule: index, position: position };
+                need_another_pass = builder.add_all(rule.head, &id_first_set, reason)
+                    || need_another_pass;
+
+                if ! id_first_set.contains(&SetEntry::Empty) {
+                    all_nullable = false;
+                    break;
+                }
+            }
+
+            if all_nullable {
+                let reason = Reason::Nullable { rule: index };
+                need_another_pass = builder.add(rule.head, SetEntry::Empty, reason)
+                    || need_another_pass;
+            }
+        }
+    }
+
+    builder.set_map
+}
+
+fn build_follow_sets(grammer: &Grammer, first_sets: &SetMap, trace: bool) -> SetMap {
+    let mut builder = SetBuilder::new(grammer, "Follow", trace);
+
+    // All follow sets start as empty
+    for id in grammer.alphabet.get_type_set(SymbolType::NonTerminal) {
+        builder.set_map.insert(*id, BTreeSet::new());
+    }
+
+    // The end of input can follow the start symbol
+    builder.trace_pass(0);
+    builder.add(grammer.start, SetEntry::End, Reason::Start);
+
+    let mut pass = 0;
+    let mut need_another_pass = true;
+    while need_another_pass {
+        need_another_pass = false;
+        pass += 1;
+        builder.trace_pass(pass);
+
+        for (index, rule) in grammer.rules.iter().enumerate() {
+            builder.trace_rule(index);
+
+            for (position, id) in rule.alternate.iter().enumerate() {
+                if ! grammer.alphabet.is_type(id, SymbolType::NonTerminal) {
+                    continue;
+                }
+
+                // Whatever can start the rest of the alternate can follow this symbol
+                let mut rest_nullable = true;
+                for source in position + 1..rule.alternate.len() {
+                    let source_first_set = first_sets.get(&rule.alternate[source]).unwrap();
+                    let reason = Reason::FollowFirst { rule: index, position: position, source: source };
+                    need_another_pass = builder.add_all(*id, source_first_set, reason)
+                        || need_another_pass;
+
+                    if ! source_first_set.contains(&SetEntry::Empty) {
+                        rest_nullable = false;
+                        break;
+                    }
+                }
+
+                // If the rest of the alternate can vanish, whatever follows the head follows this
+                if rest_nullable {
+                    let head_follow_set = builder.set_map.get(&rule.head).unwrap().clone();
+                    let reason = Reason::FollowHead { rule: index, position: position };
+                    need_another_pass = builder.add_all(*id, &head_follow_set, reason)
+                        || need_another_pass;
+                }
+            }
+        }
+    }
+
+    builder.set_map
+}
+
+/// The First set of every symbol. A symbol that can derive the empty string has `Empty` in its set.
+pub fn first_sets(grammer: &Grammer) -> SetMap {
+    build_first_sets(grammer, false)
+}
+
+/// The Follow set of every symbol, built on the First sets.
+pub fn follow_sets(grammer: &Grammer, first_sets: &SetMap) -> SetMap {
+    build_follow_sets(grammer, first_sets, false)
+}
+
+/// The same as first_sets, but prints every pass, rule and addition along the way.
+pub fn trace_first_sets(grammer: &Grammer) -> SetMap {
+    build_first_sets(grammer, true)
+}
+
+/// The same as `follow_sets`, but prints every pass, rule and addition along the way.
+pub fn trace_follow_sets(grammer: &Grammer, first_sets: &SetMap) -> SetMap {
+    build_follow_sets(grammer, first_sets, true)
+}
+
+/// The symbols that can derive the empty string.
+pub fn nullable_set(first_sets: &SetMap) -> BTreeSet<SymbolId> {
+    first_sets.iter()
+        .filter(|&(_, set)| set.contains(&SetEntry::Empty))
+        .map(|(id, _)| *id)
+        .collect()
+}
+
+/// Prints each set as `Label(Symbol) = {entries,}`, one symbol to a line.
+pub fn print_sets(label: &str, grammer: &Grammer, set_map: &SetMap) {
+    for (k, v) in set_map {
+        print!("{}({}) = {{", label, grammer.name(*k));
+        for s in v {
+            print!("{},", entry_name(grammer, s));
+        }
+        println!("}}");
+    }
+}
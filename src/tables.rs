@@ -0,0 +1,208 @@
+use std::collections::BTreeSet;
+use alphabet::{SymbolId, SymbolType};
+use grammer::Grammer;
+use lookahead::ll_1_table;
+use lr::{build_automaton, build_slr_table, Action};
+use sets::{entry_name, first_sets, follow_sets, nullable_set, SetEntry};
+
+/// How to write out a table.
+///
+/// The tables are for putting in documents, so every row and column is in declaration order.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TableFormat {
+    /// A LaTeX `tabular`.
+    Latex,
+    /// A Markdown pipe table.
+    Markdown,
+}
+
+fn escape(format: TableFormat, text: &str) -> String {
+    let mut result = String::new();
+    for c in text.chars() {
+        match (format, c) {
+            (TableFormat::Latex, '\\') => result.push_str("\\textbackslash{}"),
+            (TableFormat::Latex, '~') => result.push_str("\\textasciitilde{}"),
+            (TableFormat::Latex, '^') => result.push_str("\\textasciicircum{}"),
+            (TableFormat::Latex, '&') | (TableFormat::Latex, '%') | (TableFormat::Latex, '$')
+            | (TableFormat::Latex, '#') | (TableFormat::Latex, '_') | (TableFormat::Latex, '{')
+            | (TableFormat::Latex, '}') => {
+                result.push('\\');
+                result.push(c);
+            },
+            (TableFormat::Markdown, '\\') | (TableFormat::Markdown, '|') | (TableFormat::Markdown, '*')
+            | (TableFormat::Markdown, '_') | (TableFormat::Markdown, '`') => {
+                result.push('\\');
+                result.push(c);
+            },
+            _ => result.push(c),
+        }
+    }
+    if format == TableFormat::Latex {
+        result = result.replace("->", "$\\rightarrow$");
+    }
+    result
+}
+
+fn format_table(format: TableFormat, title: &str, header: &[String], rows: &[Vec<String>]) -> String {
+    let line = |cells: &[String]| -> Vec<String> {
+        cells.iter().map(|cell| escape(format, cell)).collect()
+    };
+
+    let mut result = String::new();
+    match format {
+        TableFormat::Latex => {
+            result.push_str(&format!("% {}\n", title));
+            result.push_str(&format!("\\begin{{tabular}}{{l|{}}}\n", "l".repeat(header.len() - 1)));
+            result.push_str(&format!("{} \\\\\n\\hline\n", line(header).join(" & ")));
+            for row in rows {
+                result.push_str(&format!("{} \\\\\n", line(row).join(" & ")));
+            }
+            result.push_str("\\end{tabular}\n");
+        },
+        TableFormat::Markdown => {
+            result.push_str(&format!("### {}\n\n", title));
+            result.push_str(&format!("| {} |\n", line(header).join(" | ")));
+            result.push_str(&format!("|{}\n", " --- |".repeat(header.len())));
+            for row in rows {
+                result.push_str(&format!("| {} |\n", line(row).join(" | ")));
+            }
+        },
+    }
+    result
+}
+
+fn names(grammer: &Grammer, ids: &BTreeSet<SymbolId>) -> Vec<String> {
+    ids.iter().map(|id| String::from(grammer.name(*id))).collect()
+}
+
+fn entry_list(grammer: &Grammer, entries: &BTreeSet<SetEntry>) -> String {
+    entries.iter()
+        .map(|entry| entry_name(grammer, entry))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn rule_text(grammer: &Grammer, rule: usize) -> String {
+    let text = grammer.rule_to_string(&grammer.rules[rule]);
+    String::from(text.trim_end_matches('.'))
+}
+
+/// The rules, numbered from 0.
+pub fn rules_table(grammer: &Grammer, format: TableFormat) -> String {
+    let header = vec![String::from("#"), String::from("Rule")];
+    let rows: Vec<Vec<String>> = (0..grammer.rules.len())
+        .map(|rule| vec![rule.to_string(), rule_text(grammer, rule)])
+        .collect();
+    format_table(format, "Rules", &header, &rows)
+}
+
+/// Whether each nonterminal is nullable, and its First and Follow sets.
+pub fn sets_table(grammer: &Grammer, format: TableFormat) -> String {
+    let first_sets = first_sets(grammer);
+    let follow_sets = follow_sets(grammer, &first_sets);
+    let nullable = nullable_set(&first_sets);
+
+    let header: Vec<String> = vec!["Nonterminal", "Nullable", "First", "Follow"].into_iter()
+        .map(String::from)
+        .collect();
+    let rows: Vec<Vec<String>> = grammer.alphabet.get_type_set(SymbolType::NonTerminal).iter()
+        .map(|id| vec![
+            String::from(grammer.name(*id)),
+            String::from(if nullable.contains(id) { "yes" } else { "no" }),
+            entry_list(grammer, &first_sets[id]),
+            entry_list(grammer, &follow_sets[id]),
+        ])
+        .collect();
+    format_table(format, "Nullable, First and Follow", &header, &rows)
+}
+
+/// The LL(1) table, with a row for each nonterminal and a column for each terminal and End.
+///
+/// A cell with more than one rule in it is an LL(1) conflict.
+pub fn ll_table(grammer: &Grammer, format: TableFormat) -> String {
+    let first_sets = first_sets(grammer);
+    let follow_sets = follow_sets(grammer, &first_sets);
+    let table = ll_1_table(grammer, &first_sets, &follow_sets);
+    let terminals = grammer.alphabet.get_type_set(SymbolType::Terminal);
+
+    let mut columns: Vec<SetEntry> = terminals.iter().cloned().map(SetEntry::Id).collect();
+    columns.push(SetEntry::End);
+
+    let mut header = vec![String::new()];
+    header.extend(columns.iter().map(|entry| entry_name(grammer, entry)));
+    let rows: Vec<Vec<String>> = grammer.alphabet.get_type_set(SymbolType::NonTerminal).iter()
+        .map(|id| {
+            let mut row = vec![String::from(grammer.name(*id))];
+            row.extend(columns.iter().map(|column| table.rules(*id, *column).iter()
+                .map(|rule| rule_text(grammer, *rule))
+                .collect::<Vec<String>>()
+                .join(", ")
+            ));
+            row
+        })
+        .collect();
+    format_table(format, "LL(1) table", &header, &rows)
+}
+
+fn action_text(action: &Action) -> String {
+    match *action {
+        Action::Shift(state) => format!("s{}", state),
+        Action::Reduce(rule) => format!("r{}", rule),
+        Action::Accept => String::from("acc"),
+        Action::Error => String::from("err"),
+    }
+}
+
+/// The SLR(1) table, with a row for each state and a column for each terminal, End and nonterminal.
+///
+/// Actions are s for shift and r for reduce by the numbered rule. A conflict precedence didn't
+/// settle shows every action, the chosen one first.
+pub fn slr_table(grammer: &Grammer, format: TableFormat) -> String {
+    let automaton = build_automaton(grammer);
+    let table = build_slr_table(grammer, &automaton);
+    let terminals = grammer.alphabet.get_type_set(SymbolType::Terminal);
+    let non_terminals = grammer.alphabet.get_type_set(SymbolType::NonTerminal);
+
+    let mut columns: Vec<SetEntry> = terminals.iter().cloned().map(SetEntry::Id).collect();
+    columns.push(SetEntry::End);
+
+    let mut header = vec![String::from("State")];
+    header.extend(columns.iter().map(|entry| entry_name(grammer, entry)));
+    header.extend(names(grammer, non_terminals));
+
+    let rows: Vec<Vec<String>> = (0..automaton.states.len())
+        .map(|state| {
+            let mut row = vec![state.to_string()];
+            row.extend(columns.iter().map(|column| {
+                let action = match table.actions[state].get(column) {
+                    Some(action) => action,
+                    None => return String::new(),
+                };
+                let mut actions = vec![action_text(action)];
+                for resolution in table.conflicts() {
+                    if resolution.state == state && resolution.lookahead == *column {
+                        actions.extend(resolution.discarded.iter().map(action_text));
+                    }
+                }
+                actions.join("/")
+            }));
+            row.extend(non_terminals.iter().map(|id| match table.gotos[state].get(id) {
+                Some(target) => target.to_string(),
+                None => String::new(),
+            }));
+            row
+        })
+        .collect();
+    format_table(format, "SLR(1) table", &header, &rows)
+}
+
+/// Prints the rules, the sets, and the LL(1) and SLR(1) tables.
+pub fn print_tables(grammer: &Grammer, format: TableFormat) {
+    let tables = [
+        rules_table(grammer, format),
+        sets_table(grammer, format),
+        ll_table(grammer, format),
+        slr_table(grammer, format),
+    ];
+    print!("{}", tables.join("\n"));
+}
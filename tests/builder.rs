@@ -0,0 +1,38 @@
+extern crate grammer_sets;
+
+use grammer_sets::{build_lexer, GrammarBuilder};
+
+fn messages(builder: &GrammarBuilder) -> Vec<String> {
+    builder.build()
+        .err()
+        .expect("the grammer is rejected")
+        .into_iter()
+        .map(|diagnostic| diagnostic.message)
+        .collect()
+}
+
+#[test]
+fn builds_a_grammer_with_literals() {
+    let grammer = GrammarBuilder::new()
+        .rule("S", &["\"a\\\"b\"", "\"\\\\\""])
+        .build()
+        .expect("the literals are valid");
+    let tokens = build_lexer(&grammer).lex("a\"b\\").expect("the text lexes");
+    assert_eq!(tokens.len(), 2);
+}
+
+// A literal name has to be one the text format could produce, or the lexer can't undo it
+#[test]
+fn rejects_malformed_literals() {
+    for name in &["\"", "\"\"", "\"a b", "\"a\"b\"", "\"a\\\""] {
+        let messages = messages(GrammarBuilder::new().rule("S", &[name]));
+        assert_eq!(messages.len(), 1, "{} is rejected once", name);
+        assert!(messages[0].starts_with(&format!("{} is not a valid name. A literal", name)));
+    }
+}
+
+#[test]
+fn rejects_a_literal_as_a_head() {
+    let messages = messages(GrammarBuilder::new().rule("\"a\"", &["b"]));
+    assert!(messages[0].starts_with("\"a\" is not a valid name. Names start"));
+}
@@ -0,0 +1,74 @@
+extern crate grammer_sets;
+
+use grammer_sets::{build_lexer, check, load, parse_tokens, Grammer, ParseTree, SymbolId};
+
+fn regex_grammer() -> Grammer {
+    let raw_grammer = load("tests/regex.grammer").expect("tests/regex.grammer parses");
+    check(&raw_grammer, false).unwrap_or_else(|_| panic!("tests/regex.grammer checks"))
+}
+
+fn tokens(grammer: &Grammer, text: &str) -> Vec<SymbolId> {
+    build_lexer(grammer).lex(text)
+        .expect("the text lexes")
+        .iter()
+        .map(|token| token.id)
+        .collect()
+}
+
+fn leaves(tree: &ParseTree, result: &mut Vec<SymbolId>) {
+    match *tree {
+        ParseTree::Leaf(id) => result.push(id),
+        ParseTree::Node { ref children, .. } => {
+            for child in children {
+                leaves(child, result);
+            }
+        }
+    }
+}
+
+// Expr -> Expr Expr makes almost every split of a long regex a possible parse, which used to
+// take time exponential in the length of the sentence
+#[test]
+fn parses_a_long_ambiguous_sentence() {
+    let grammer = regex_grammer();
+    let text = "(ab|cd|ef)* [a-z\\d]+ (x|y|z)? abc [a-f] (a|b)+ q\\. w* (mn)+ [\\wk]";
+    let tokens = tokens(&grammer, text);
+    assert!(tokens.len() >= 30);
+
+    let tree = parse_tokens(&grammer, &tokens).expect("the sentence is a regex");
+    assert_eq!(tree.symbol(&grammer), grammer.start);
+
+    let mut found = Vec::new();
+    leaves(&tree, &mut found);
+    assert_eq!(found, tokens);
+}
+
+#[test]
+fn builds_empty_subtrees() {
+    let grammer = regex_grammer();
+    let tokens = tokens(&grammer, "[a]");
+    let tree = parse_tokens(&grammer, &tokens).expect("[a] is a regex");
+
+    // MatchListCons -> Empty ends the list
+    let match_list = match tree {
+        ParseTree::Node { ref children, .. } => &children[1],
+        ParseTree::Leaf(_) => panic!("the root is a nonterminal"),
+    };
+    match *match_list {
+        ParseTree::Node { ref children, .. } => match children[1] {
+            ParseTree::Node { rule, ref children } => {
+                assert_eq!(grammer.rule_to_string(&grammer.rules[rule]), "MatchListCons -> Empty.");
+                assert!(children.is_empty());
+            },
+            ParseTree::Leaf(_) => panic!("MatchListCons is a nonterminal"),
+        },
+        ParseTree::Leaf(_) => panic!("MatchList is a nonterminal"),
+    }
+}
+
+#[test]
+fn rejects_a_sentence_not_in_the_language() {
+    let grammer = regex_grammer();
+    let tokens = tokens(&grammer, "(ab|c");
+    assert!(parse_tokens(&grammer, &tokens).is_none());
+}
@@ -0,0 +1,67 @@
+extern crate grammer_sets;
+
+use grammer_sets::{check, parse, Grammer};
+
+fn desugared(text: &str) -> Grammer {
+    let raw_grammer = parse(text.as_bytes()).expect("the grammer parses");
+    check(&raw_grammer, true).unwrap_or_else(|_| panic!("the grammer checks"))
+}
+
+fn rules(grammer: &Grammer) -> Vec<String> {
+    grammer.rules.iter()
+        .map(|rule| grammer.rule_to_string(rule))
+        .collect()
+}
+
+// Each form gets a fresh Head'N nonterminal, numbered in the order they are needed. The rules
+// made up for a form go after the written ones, and those of a nested group come before the
+// rule that uses it
+#[test]
+fn lowers_every_form() {
+    let grammer = desugared(":Rules:\nS -> a [ b ] { c } d+ e? ( f | g ( h | i ) ) ( j k ).\n");
+    assert_eq!(rules(&grammer), vec![
+        "S -> a S'1 S'2 d S'3 S'4 S'5 j k.",
+        "S'1 -> b.",
+        "S'1 -> Empty.",
+        "S'2 -> c S'2.",
+        "S'2 -> Empty.",
+        "S'3 -> d S'3.",
+        "S'3 -> Empty.",
+        "S'4 -> e.",
+        "S'4 -> Empty.",
+        "S'5 -> f.",
+        "S'6 -> h.",
+        "S'6 -> i.",
+        "S'5 -> g S'6.",
+    ]);
+}
+
+// A named child has to stay a single symbol, so a group or a + gets a nonterminal of its own
+#[test]
+fn named_children_stay_one_symbol() {
+    let grammer = desugared(":Rules:\nS -> a pair:( b c ) one:d many:e+.\n");
+    assert_eq!(rules(&grammer), vec![
+        "S -> a S'1 d S'3.",
+        "S'1 -> b c.",
+        "S'2 -> e S'2.",
+        "S'2 -> Empty.",
+        "S'3 -> e S'2.",
+    ]);
+
+    let names: Vec<Option<&str>> = grammer.rules[0].child_names.iter()
+        .map(|name| name.as_ref().map(String::as_str))
+        .collect();
+    assert_eq!(names, vec![None, Some("pair"), Some("one"), Some("many")]);
+    assert!(grammer.rules[1..].iter().all(|rule| rule.child_names.iter().all(Option::is_none)));
+}
+
+#[test]
+fn fresh_names_skip_written_ones() {
+    let grammer = desugared(":Rules:\nT -> T'1 [ x ].\nT'1 -> y.\n");
+    assert_eq!(rules(&grammer), vec![
+        "T -> T'1 T'2.",
+        "T'1 -> y.",
+        "T'2 -> x.",
+        "T'2 -> Empty.",
+    ]);
+}
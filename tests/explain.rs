@@ -0,0 +1,37 @@
+use std::process::Command;
+
+fn why(args: &[&str]) -> Vec<String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_grammer_sets"))
+        .args(["-i", "tests/why.grammer"].iter().chain(args))
+        .output()
+        .expect("the binary runs");
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).expect("the output is UTF-8")
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+// The sets are built rule by rule, so x reaches First(S) through A and B before S -> x is seen
+#[test]
+fn why_first_gives_the_shortest_chain() {
+    assert_eq!(why(&["--why-first", "S", "x"]), vec!["x ∈ First(S) because of rule `S -> x.`"]);
+}
+
+#[test]
+fn why_follow_gives_the_shortest_chain() {
+    assert_eq!(why(&["--why-follow", "A", "c"]), vec!["c ∈ Follow(A) because of rule `S -> A c.`"]);
+    assert_eq!(why(&["--why-follow", "B", "c"]), vec![
+        "c ∈ Follow(B) because of rule `A -> B.`, and c ∈ Follow(A)",
+        "c ∈ Follow(A) because of rule `S -> A c.`",
+    ]);
+}
+
+#[test]
+fn chains_run_through_first_sets() {
+    assert_eq!(why(&["--why-first", "X", "x"]), vec![
+        "x ∈ First(X) because of rule `X -> A.`, and x ∈ First(A)",
+        "x ∈ First(A) because of rule `A -> B.`, and x ∈ First(B)",
+        "x ∈ First(B) because of rule `B -> x.`",
+    ]);
+}
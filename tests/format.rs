@@ -0,0 +1,48 @@
+extern crate grammer_sets;
+
+use grammer_sets::format_text;
+
+fn format(text: &str) -> String {
+    let formatted = format_text(text.as_bytes()).expect("the grammer formats");
+    let again = format_text(formatted.as_bytes()).expect("the formatted grammer formats");
+    assert_eq!(again, formatted, "formatting is not idempotent");
+    formatted
+}
+
+// A comment after the '.' of a rule belongs to that rule, not to whatever comes next
+#[test]
+fn line_end_comments_stay_with_their_rule() {
+    let text = "\
+:Rules:
+E -> a F. # E needs an F
+F -> b /* b or c */
+  | c.
+";
+    let expected = "\
+:Rules:
+# E needs an F
+E -> a F.
+
+/* b or c */
+F -> b
+   | c.
+";
+    assert_eq!(format(text), expected);
+}
+
+#[test]
+fn line_end_comment_on_the_last_rule_is_not_trailing() {
+    let text = "\
+:Rules:
+S -> a. // the only rule
+# the end of the file
+";
+    let expected = "\
+:Rules:
+// the only rule
+S -> a.
+
+# the end of the file
+";
+    assert_eq!(format(text), expected);
+}
@@ -0,0 +1,78 @@
+extern crate grammer_sets;
+
+use std::fs;
+use grammer_sets::{check, load, SymbolType};
+
+fn names(path: &str, sym_type: SymbolType) -> Vec<String> {
+    let raw_grammer = load(path).expect("the grammer and its includes parse");
+    let grammer = check(&raw_grammer, false).unwrap_or_else(|_| panic!("{} checks", path));
+    grammer.alphabet.get_type_set(sym_type).iter()
+        .map(|id| String::from(grammer.name(*id)))
+        .collect()
+}
+
+// Each diagnostic as it is printed, from loading the grammer or else from checking it
+fn errors(path: &str) -> Vec<String> {
+    let diagnostics = match load(path) {
+        Ok(raw_grammer) => check(&raw_grammer, false).err().expect("the grammer has errors"),
+        Err(diagnostics) => diagnostics,
+    };
+    diagnostics.iter().map(|diagnostic| diagnostic.to_string()).collect()
+}
+
+fn canonical(path: &str) -> String {
+    fs::canonicalize(path).unwrap().display().to_string()
+}
+
+// statements.grammer includes expr.grammer as Expr, which includes tokens.grammer without a
+// prefix of its own, so everything from both gets Expr_
+#[test]
+fn included_names_are_prefixed() {
+    assert_eq!(
+        names("tests/include/statements.grammer", SymbolType::Terminal),
+        vec!["Semi", "Print", "Expr_Plus", "Expr_Star", "Expr_LParen", "Expr_RParen", "Expr_Id"]
+    );
+    assert_eq!(
+        names("tests/include/statements.grammer", SymbolType::NonTerminal),
+        vec!["Stmts", "Stmt", "Expr_E", "Expr_Eprime", "Expr_T", "Expr_Tprime", "Expr_F", "Stmts'1"]
+    );
+}
+
+#[test]
+fn names_are_not_prefixed_without_as() {
+    assert_eq!(
+        names("tests/include/expr.grammer", SymbolType::Terminal),
+        vec!["Plus", "Star", "LParen", "RParen", "Id"]
+    );
+}
+
+#[test]
+fn missing_include_is_an_error() {
+    assert_eq!(
+        errors("tests/include/missing.grammer"),
+        vec!["ERROR: tests/include/missing.grammer:4:11: could not read the included file tests/include/nowhere.grammer"]
+    );
+}
+
+// The location of a problem in an included file is in that file, not the one including it
+#[test]
+fn errors_in_an_included_file_point_into_it() {
+    assert_eq!(
+        errors("tests/include/undeclared.grammer"),
+        vec!["ERROR: tests/include/undeclared_part.grammer:2:6: Part_b was used in a rule alternate, but was not declared"]
+    );
+}
+
+#[test]
+fn include_cycle_is_an_error() {
+    let cycle = format!(
+        "{} -> {} -> {}",
+        canonical("tests/include/cycle_a.grammer"),
+        canonical("tests/include/cycle_b.grammer"),
+        canonical("tests/include/cycle_a.grammer")
+    );
+    assert_eq!(
+        errors("tests/include/cycle_a.grammer"),
+        vec![format!("ERROR: tests/include/cycle_b.grammer:4:11: including cycle_a.grammer forms a cycle, {}", cycle)]
+    );
+}
@@ -0,0 +1,65 @@
+extern crate grammer_sets;
+
+use grammer_sets::{build_lexer, check, parse, Diagnostic, Grammer};
+
+// "if" is written as a literal in the rules, and Id and Num are regexes
+fn grammer() -> Grammer {
+    let text = "\
+:Tokens:
+Id = /[a-z]+/
+Num = /[0-9]+/
+%skip /\\s+/
+%skip /#[^\\n]*/
+
+:Rules:
+S -> \"if\" Id S | Id | Num.
+";
+    let raw_grammer = parse(text.as_bytes()).expect("the grammer parses");
+    check(&raw_grammer, false).unwrap_or_else(|_| panic!("the grammer checks"))
+}
+
+fn lex(grammer: &Grammer, text: &str) -> Result<Vec<(String, usize, usize)>, Vec<Diagnostic>> {
+    let tokens = build_lexer(grammer).lex(text)?;
+    Ok(tokens.iter()
+        .map(|token| (String::from(grammer.name(token.id)), token.start, token.end))
+        .collect())
+}
+
+fn names(grammer: &Grammer, text: &str) -> Vec<String> {
+    lex(grammer, text).expect("the text lexes").into_iter()
+        .map(|(name, _, _)| name)
+        .collect()
+}
+
+#[test]
+fn longest_match_wins() {
+    let grammer = grammer();
+    assert_eq!(names(&grammer, "iffy"), vec!["Id"]);
+    assert_eq!(names(&grammer, "if42"), vec!["\"if\"", "Num"]);
+}
+
+// Keywords win over identifiers
+#[test]
+fn literal_beats_regex_of_the_same_length() {
+    assert_eq!(names(&grammer(), "if"), vec!["\"if\""]);
+}
+
+#[test]
+fn skips_are_dropped() {
+    let grammer = grammer();
+    let tokens = lex(&grammer, "  if x # a comment\n 12").expect("the text lexes");
+    assert_eq!(tokens, vec![
+        (String::from("\"if\""), 2, 4),
+        (String::from("Id"), 5, 6),
+        (String::from("Num"), 20, 22),
+    ]);
+}
+
+#[test]
+fn unmatched_text_is_an_error_at_its_position() {
+    let diagnostics = lex(&grammer(), "if x\n  y ~z w").expect_err("~ matches nothing");
+    assert_eq!(diagnostics, vec![Diagnostic {
+        location: Some(String::from("line 2, column 5")),
+        message: String::from("no token matches the text starting with '~z'"),
+    }]);
+}
@@ -0,0 +1,81 @@
+extern crate grammer_sets;
+
+use std::collections::BTreeSet;
+use grammer_sets::{check, first_k_sets, first_sets, follow_k_sets, follow_sets, ll_k_conflicts, load};
+use grammer_sets::{GrammarBuilder, Grammer, SetEntry, SetMap, TrieMap};
+
+static GRAMMERS: &[&str] = &[
+    "tests/alternatives.grammer",
+    "tests/ebnf.grammer",
+    "tests/order.grammer",
+    "tests/precedence.grammer",
+    "tests/regex.grammer",
+    "tests/tufts.grammer",
+    "tests/tufts_ll.grammer",
+    "tests/uaf_example.grammer",
+];
+
+fn load_grammer(path: &str) -> Grammer {
+    let raw_grammer = load(path).unwrap_or_else(|_| panic!("{} parses", path));
+    check(&raw_grammer, true).unwrap_or_else(|_| panic!("{} checks", path))
+}
+
+// With k = 1 every string is one terminal or none, and none is Empty in a first set and End in
+// a follow set
+fn as_set_map(trie_map: &TrieMap, empty_entry: SetEntry) -> SetMap {
+    trie_map.iter()
+        .map(|(id, trie)| {
+            let entries: BTreeSet<SetEntry> = trie.sequences().iter()
+                .map(|sequence| sequence.first().map_or(empty_entry, |id| SetEntry::Id(*id)))
+                .collect();
+            (*id, entries)
+        })
+        .collect()
+}
+
+#[test]
+fn k_1_sets_are_the_first_and_follow_sets() {
+    for path in GRAMMERS {
+        let grammer = load_grammer(path);
+        let first = first_sets(&grammer);
+        let follow = follow_sets(&grammer, &first);
+        let first_k = first_k_sets(&grammer, 1);
+        let follow_k = follow_k_sets(&grammer, &first_k, 1);
+
+        for (id, set) in as_set_map(&first_k, SetEntry::Empty) {
+            assert_eq!(set, first[&id], "First({}) of {}", grammer.name(id), path);
+        }
+        for (id, set) in as_set_map(&follow_k, SetEntry::End) {
+            assert_eq!(set, follow[&id], "Follow({}) of {}", grammer.name(id), path);
+        }
+    }
+}
+
+// An assignment and a call both start with a name, and only the token after it tells them apart
+#[test]
+fn ll_2_grammer_conflicts_only_at_k_1() {
+    let grammer = GrammarBuilder::new()
+        .terminal("Id")
+        .terminal("Assign")
+        .terminal("LParen")
+        .terminal("RParen")
+        .rule("Stmt", &["Id", "Assign", "Expr"])
+        .rule("Stmt", &["Id", "LParen", "RParen"])
+        .rule("Expr", &["Id"])
+        .build()
+        .unwrap();
+    let conflicts = |k: usize| {
+        let first_k = first_k_sets(&grammer, k);
+        let follow_k = follow_k_sets(&grammer, &first_k, k);
+        ll_k_conflicts(&grammer, &first_k, &follow_k, k)
+    };
+
+    let id = grammer.alphabet.id_for_name("Id").unwrap();
+    let at_1 = conflicts(1);
+    assert_eq!(at_1.len(), 1);
+    assert_eq!(grammer.name(at_1[0].head), "Stmt");
+    assert_eq!(at_1[0].rules, (0, 1));
+    assert_eq!(at_1[0].lookaheads, vec![vec![id]]);
+
+    assert!(conflicts(2).is_empty());
+}
@@ -0,0 +1,56 @@
+use std::fs::File;
+use std::io::Read;
+use std::process::Command;
+
+// tests/order.grammer declares its symbols out of alphabetical order, and the output has to keep
+// to that order: declared terminals, then declared nonterminals, then undeclared heads, the
+// nonterminals made up for EBNF, and last literals and inferred terminals as they were written
+fn run(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_grammer_sets"))
+        .args(["-i", "tests/order.grammer", "--infer-terminals"].iter().chain(args))
+        .output()
+        .expect("the binary runs");
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).expect("the output is UTF-8")
+}
+
+fn golden(path: &str) -> String {
+    let mut text = String::new();
+    File::open(path).expect("the golden file exists")
+        .read_to_string(&mut text)
+        .expect("the golden file is UTF-8");
+    text
+}
+
+#[test]
+fn sets_are_in_declaration_order() {
+    assert_eq!(run(&[]), golden("tests/order.sets.txt"));
+}
+
+#[test]
+fn tables_are_in_declaration_order() {
+    assert_eq!(run(&["--format", "markdown"]), golden("tests/order.md"));
+}
+
+// The JSON sets come in the same order as the printed ones
+#[cfg(feature = "serde")]
+#[test]
+fn json_sets_are_in_declaration_order() {
+    let json = run(&["--format", "json"]);
+    let follow = json.find("\"follow\"").expect("there are follow sets");
+    let json_symbols = |text: &str| -> Vec<String> {
+        text.lines()
+            .filter_map(|line| line.trim().strip_prefix("\"symbol\": "))
+            .map(|name| String::from(name.trim_end_matches(',')))
+            .collect()
+    };
+    let printed_symbols = |label: &str| -> Vec<String> {
+        golden("tests/order.sets.txt").lines()
+            .filter_map(|line| line.strip_prefix(label))
+            .map(|line| format!("\"{}\"", line[..line.find(") = ").unwrap()].replace('"', "\\\"")))
+            .collect()
+    };
+
+    assert_eq!(json_symbols(&json[..follow]), printed_symbols("First("));
+    assert_eq!(json_symbols(&json[follow..]), printed_symbols("Follow("));
+}
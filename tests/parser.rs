@@ -0,0 +1,31 @@
+extern crate grammer_sets;
+
+use grammer_sets::parse;
+
+fn errors(text: &str) -> Vec<String> {
+    parse(text.as_bytes()).expect_err("the grammer is rejected").iter()
+        .map(|diagnostic| diagnostic.message.clone())
+        .collect()
+}
+
+// The token that stands where the names of an alternate should start is named, with its position
+#[test]
+fn missing_alternate_names_the_bad_token() {
+    assert_eq!(
+        errors(":Rules:\nS -> a.\nT -> | b.\n"),
+        vec!["'|' at line 3, column 6 is not a name. Expected a whitespace separated list of names"]
+    );
+    assert_eq!(
+        errors(":Rules:\nS ->"),
+        vec!["the input ends at line 2, column 5. Expected a whitespace separated list of names"]
+    );
+}
+
+#[test]
+fn bad_char_in_a_name_is_pointed_at() {
+    assert_eq!(
+        errors(":Rules:\nS -> a$b.\n"),
+        vec!["'$' at line 2, column 7 cannot be used in a name. Names start with a letter, digit or '_', \
+              and after that may also contain '-' and '''"]
+    );
+}
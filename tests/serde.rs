@@ -0,0 +1,50 @@
+#![cfg(feature = "serde")]
+
+extern crate grammer_sets;
+extern crate serde_json;
+
+use grammer_sets::{first_sets, follow_sets, ll_1_table, GrammarBuilder, LlTable};
+use grammer_sets::json::report;
+
+// The keys of the table are pairs, which a JSON object can't have
+#[test]
+fn ll_table_round_trips_through_json() {
+    let grammer = GrammarBuilder::new()
+        .terminal("a")
+        .terminal("b")
+        .rule("S", &["a", "T"])
+        .rule("S", &["a"])
+        .rule("T", &["b"])
+        .rule("T", &[])
+        .build()
+        .unwrap();
+    let first_sets = first_sets(&grammer);
+    let follow_sets = follow_sets(&grammer, &first_sets);
+    let table = ll_1_table(&grammer, &first_sets, &follow_sets);
+    assert_eq!(table.conflict_count(), 1);
+
+    let json = serde_json::to_string(&table).expect("the table serializes");
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let entries = value.as_array().expect("the table is a list of entries");
+    assert_eq!(entries.len(), 3);
+    for entry in entries {
+        assert!(entry["head"].is_number() && entry["rules"].is_array());
+    }
+
+    let again: LlTable = serde_json::from_str(&json).expect("the table deserializes");
+    assert_eq!(again, table);
+}
+
+#[test]
+fn empty_ll_lookahead_is_end() {
+    let grammer = GrammarBuilder::new()
+        .rule("S", &["T"])
+        .rule("S", &["U"])
+        .rule("T", &[])
+        .rule("U", &[])
+        .build()
+        .unwrap();
+    let report = report(&grammer, 1);
+    assert_eq!(report.ll_conflicts.len(), 1);
+    assert_eq!(report.ll_conflicts[0].lookaheads, vec![vec![String::from("End")]]);
+}
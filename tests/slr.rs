@@ -0,0 +1,59 @@
+use std::process::Command;
+
+// tests/precedence.grammer is ambiguous, and every one of its conflicts is settled by precedence
+fn report() -> Vec<String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_grammer_sets"))
+        .args(["-i", "tests/precedence.grammer", "--slr"])
+        .output()
+        .expect("the binary runs");
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).expect("the output is UTF-8")
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+// The part of the report line after the state, for the state that can reduce the rule
+fn resolution(report: &[String], lookahead: &str, rule: &str) -> String {
+    let on = format!(" on {}: ", lookahead);
+    let line = report.iter()
+        .find(|line| line.contains(&on) && line.contains(&format!("`{}`", rule)))
+        .unwrap_or_else(|| panic!("there is a resolution on {} for {}", lookahead, rule));
+    String::from(&line[line.find(&on).unwrap() + on.len()..])
+}
+
+#[test]
+fn left_associative_reduces() {
+    let line = resolution(&report(), "\"+\"", "E -> E \"+\" E.");
+    assert!(line.starts_with("reduce `E -> E \"+\" E.` over shift to state "), "{}", line);
+    assert!(line.ends_with(", \"+\" is %left"), "{}", line);
+}
+
+#[test]
+fn right_associative_shifts() {
+    let line = resolution(&report(), "\"^\"", "E -> E \"^\" E.");
+    assert!(line.starts_with("shift to state "), "{}", line);
+    assert!(line.ends_with(" over reduce `E -> E \"^\" E.`, \"^\" is %right"), "{}", line);
+}
+
+#[test]
+fn non_associative_is_an_error() {
+    let line = resolution(&report(), "\"<\"", "E -> E \"<\" E.");
+    assert!(line.starts_with("error over shift to state "), "{}", line);
+    assert!(line.ends_with(" and reduce `E -> E \"<\" E.`, \"<\" is %nonassoc"), "{}", line);
+}
+
+// Without %prec UMinus the rule would have the precedence of "-", and "*" would shift
+#[test]
+fn prec_overrides_the_rule_precedence() {
+    let line = resolution(&report(), "\"*\"", "E -> \"-\" E.");
+    assert!(line.starts_with("reduce `E -> \"-\" E.` over shift to state "), "{}", line);
+    assert!(line.ends_with(", the rule binds tighter than \"*\""), "{}", line);
+}
+
+#[test]
+fn precedence_grammer_is_slr_1() {
+    let report = report();
+    assert!(report.iter().all(|line| ! line.starts_with("Conflict")));
+    assert_eq!(report.last().map(String::as_str), Some("The grammer is SLR(1)"));
+}